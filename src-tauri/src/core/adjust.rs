@@ -0,0 +1,286 @@
+use image::{DynamicImage, Rgba};
+use serde::{Deserialize, Serialize};
+
+/// 颜色调整参数，未设置的项不生效。按 gamma → 亮度 → 对比度 → 饱和度 → 色相 → 灰度 → 复古 → 反相的顺序应用。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdjustParams {
+    /// -1–1，0 不变。
+    pub brightness: Option<f32>,
+    /// -1–1，0 不变。
+    pub contrast: Option<f32>,
+    /// -1–1，0 不变，-1 为完全去色。
+    pub saturation: Option<f32>,
+    /// 色相旋转角度。
+    pub hue_rotate: Option<f32>,
+    /// 大于 0，1 不变；大于 1 提亮暗部。
+    pub gamma: Option<f32>,
+    pub grayscale: Option<bool>,
+    pub sepia: Option<bool>,
+    pub invert: Option<bool>,
+}
+
+type Matrix = [[f32; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+impl AdjustParams {
+    /// 以 `base` 补全未设置的项。
+    pub fn fill_from(&mut self, base: &AdjustParams) {
+        self.brightness = self.brightness.or(base.brightness);
+        self.contrast = self.contrast.or(base.contrast);
+        self.saturation = self.saturation.or(base.saturation);
+        self.hue_rotate = self.hue_rotate.or(base.hue_rotate);
+        self.gamma = self.gamma.or(base.gamma);
+        self.grayscale = self.grayscale.or(base.grayscale);
+        self.sepia = self.sepia.or(base.sepia);
+        self.invert = self.invert.or(base.invert);
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.brightness.unwrap_or(0.0) == 0.0
+            && self.contrast.unwrap_or(0.0) == 0.0
+            && self.saturation.unwrap_or(0.0) == 0.0
+            && self.hue_rotate.unwrap_or(0.0).rem_euclid(360.0) == 0.0
+            && self.gamma.unwrap_or(1.0) == 1.0
+            && !self.grayscale.unwrap_or(false)
+            && !self.sepia.unwrap_or(false)
+            && !self.invert.unwrap_or(false)
+    }
+}
+
+pub fn apply_adjust(img: DynamicImage, params: &AdjustParams) -> DynamicImage {
+    if params.is_identity() {
+        return img;
+    }
+    let has_alpha = img.color().has_alpha();
+    let lut = tone_lut(params);
+    let matrix = color_matrix(params);
+    let invert = params.invert.unwrap_or(false);
+
+    let mut rgba = img.to_rgba8();
+    for pixel in rgba.pixels_mut() {
+        let Rgba([r, g, b, a]) = *pixel;
+        let mut rgb = [
+            lut[usize::from(r)],
+            lut[usize::from(g)],
+            lut[usize::from(b)],
+        ];
+        if let Some(matrix) = &matrix {
+            let source = rgb.map(f32::from);
+            for (channel, row) in rgb.iter_mut().zip(matrix) {
+                let value = row[0] * source[0] + row[1] * source[1] + row[2] * source[2];
+                *channel = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        if invert {
+            rgb = rgb.map(|channel| 255 - channel);
+        }
+        *pixel = Rgba([rgb[0], rgb[1], rgb[2], a]);
+    }
+
+    let img = DynamicImage::ImageRgba8(rgba);
+    if has_alpha {
+        img
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    }
+}
+
+/// 逐通道的 gamma、亮度与对比度查找表。
+fn tone_lut(params: &AdjustParams) -> [u8; 256] {
+    let gamma = params.gamma.unwrap_or(1.0);
+    let brightness = params.brightness.unwrap_or(0.0);
+    let contrast = 1.0 + params.contrast.unwrap_or(0.0);
+    let mut lut = [0u8; 256];
+    for (index, entry) in lut.iter_mut().enumerate() {
+        let mut value = (index as f32 / 255.0).powf(1.0 / gamma);
+        value += brightness;
+        value = (value - 0.5) * contrast + 0.5;
+        *entry = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    lut
+}
+
+/// 饱和度、色相、灰度与复古合成的颜色矩阵；都未设置时返回 None。
+fn color_matrix(params: &AdjustParams) -> Option<Matrix> {
+    let mut matrix = IDENTITY;
+    let mut changed = false;
+    let mut then = |step: Matrix| {
+        matrix = multiply(&step, &matrix);
+        changed = true;
+    };
+
+    if let Some(saturation) = params.saturation.filter(|s| *s != 0.0) {
+        let s = 1.0 + saturation;
+        then([
+            [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+        ]);
+    }
+    if let Some(degrees) = params.hue_rotate.filter(|d| d.rem_euclid(360.0) != 0.0) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        then([
+            [
+                0.213 + cos * 0.787 - sin * 0.213,
+                0.715 - cos * 0.715 - sin * 0.715,
+                0.072 - cos * 0.072 + sin * 0.928,
+            ],
+            [
+                0.213 - cos * 0.213 + sin * 0.143,
+                0.715 + cos * 0.285 + sin * 0.140,
+                0.072 - cos * 0.072 - sin * 0.283,
+            ],
+            [
+                0.213 - cos * 0.213 - sin * 0.787,
+                0.715 - cos * 0.715 + sin * 0.715,
+                0.072 + cos * 0.928 + sin * 0.072,
+            ],
+        ]);
+    }
+    if params.grayscale.unwrap_or(false) {
+        let luma = [0.2126, 0.7152, 0.0722];
+        then([luma, luma, luma]);
+    }
+    if params.sepia.unwrap_or(false) {
+        then([
+            [0.393, 0.769, 0.189],
+            [0.349, 0.686, 0.168],
+            [0.272, 0.534, 0.131],
+        ]);
+    }
+    changed.then_some(matrix)
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut out = [[0.0; 3]; 3];
+    for (row, out_row) in out.iter_mut().enumerate() {
+        for (col, cell) in out_row.iter_mut().enumerate() {
+            *cell = (0..3).map(|k| a[row][k] * b[k][col]).sum();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn adjust(pixel: [u8; 3], params: &AdjustParams) -> [u8; 3] {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb(pixel)));
+        apply_adjust(img, params).to_rgb8().get_pixel(0, 0).0
+    }
+
+    #[test]
+    fn neutral_values_are_identity() {
+        assert!(AdjustParams::default().is_identity());
+        let neutral = AdjustParams {
+            brightness: Some(0.0),
+            contrast: Some(0.0),
+            saturation: Some(0.0),
+            hue_rotate: Some(360.0),
+            gamma: Some(1.0),
+            grayscale: Some(false),
+            sepia: Some(false),
+            invert: Some(false),
+        };
+        assert!(neutral.is_identity());
+        for pixel in [[0, 0, 0], [12, 200, 97], [255, 255, 255]] {
+            assert_eq!(adjust(pixel, &neutral), pixel);
+        }
+
+        let changed = [
+            AdjustParams {
+                brightness: Some(0.1),
+                ..Default::default()
+            },
+            AdjustParams {
+                hue_rotate: Some(-90.0),
+                ..Default::default()
+            },
+            AdjustParams {
+                gamma: Some(2.2),
+                ..Default::default()
+            },
+            AdjustParams {
+                sepia: Some(true),
+                ..Default::default()
+            },
+        ];
+        for params in changed {
+            assert!(!params.is_identity(), "{params:?}");
+        }
+    }
+
+    #[test]
+    fn inverts_colour_but_not_alpha() {
+        let invert = AdjustParams {
+            invert: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(adjust([0, 128, 255], &invert), [255, 127, 0]);
+
+        let img =
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, Rgba([10, 20, 30, 40])));
+        let out = apply_adjust(img, &invert);
+        assert_eq!(out.color(), image::ColorType::Rgba8);
+        assert_eq!(out.to_rgba8().get_pixel(0, 0).0, [245, 235, 225, 40]);
+    }
+
+    #[test]
+    fn grayscale_uses_luma_weights() {
+        let grayscale = AdjustParams {
+            grayscale: Some(true),
+            ..Default::default()
+        };
+        for [r, g, b] in [[255, 0, 0], [0, 255, 0], [0, 0, 255], [200, 100, 50]] {
+            let luma = (0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b))
+                .round() as u8;
+            let [out_r, out_g, out_b] = adjust([r, g, b], &grayscale);
+            assert_eq!(out_r, out_g);
+            assert_eq!(out_g, out_b);
+            assert!(out_r.abs_diff(luma) <= 1, "{r},{g},{b}: {out_r} vs {luma}");
+        }
+
+        // 完全去饱和与灰度只差权重的取整。
+        let desaturate = AdjustParams {
+            saturation: Some(-1.0),
+            ..Default::default()
+        };
+        let [r, g, b] = adjust([200, 100, 50], &desaturate);
+        assert!(r.abs_diff(g) <= 1 && g.abs_diff(b) <= 1);
+    }
+
+    #[test]
+    fn brightness_and_contrast_follow_the_tone_curve() {
+        let brighter = AdjustParams {
+            brightness: Some(0.2),
+            ..Default::default()
+        };
+        assert_eq!(adjust([0, 100, 255], &brighter), [51, 151, 255]);
+
+        let flat = AdjustParams {
+            contrast: Some(-1.0),
+            ..Default::default()
+        };
+        assert_eq!(adjust([0, 77, 255], &flat), [128, 128, 128]);
+    }
+
+    #[test]
+    fn fill_from_keeps_explicit_values() {
+        let mut params = AdjustParams {
+            brightness: Some(0.5),
+            ..Default::default()
+        };
+        params.fill_from(&AdjustParams {
+            brightness: Some(-0.5),
+            invert: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(params.brightness, Some(0.5));
+        assert_eq!(params.invert, Some(true));
+        assert_eq!(params.contrast, None);
+    }
+}
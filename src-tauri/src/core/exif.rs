@@ -0,0 +1,309 @@
+use image::metadata::Orientation;
+
+const ORIENTATION_TAG: u16 = 0x0112;
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// EXIF Orientation 在 JPEG 字节中的位置。
+enum OrientationSlot {
+    /// IFD0 中已有 Orientation 标签，值（SHORT）位于 `offset`。
+    Entry { offset: usize, little_endian: bool },
+    /// 有 EXIF 段但没有 Orientation 标签。
+    MissingTag,
+    /// 没有 EXIF 段，可在 `insert_at` 处插入新的 APP1。
+    NoExif { insert_at: usize },
+}
+
+pub fn is_jpeg(data: &[u8]) -> bool {
+    data.starts_with(&[0xFF, 0xD8])
+}
+
+/// 读取 JPEG 的 EXIF Orientation，缺失或无法解析时视为不变换。
+pub fn jpeg_orientation(data: &[u8]) -> Orientation {
+    match locate_orientation(data) {
+        Some(OrientationSlot::Entry {
+            offset,
+            little_endian,
+        }) => read_u16(data, offset, little_endian)
+            .and_then(|value| u8::try_from(value).ok())
+            .and_then(Orientation::from_exif)
+            .unwrap_or(Orientation::NoTransforms),
+        _ => Orientation::NoTransforms,
+    }
+}
+
+/// 改写 JPEG 的 EXIF Orientation 而不触碰图像数据。已有标签时原地改写，没有 EXIF 段时插入一个
+/// 只含 Orientation 的 APP1；EXIF 中缺少该标签或结构无法解析时返回 None。
+pub fn with_jpeg_orientation(data: &[u8], orientation: Orientation) -> Option<Vec<u8>> {
+    let value = u16::from(orientation.to_exif());
+    match locate_orientation(data)? {
+        OrientationSlot::Entry {
+            offset,
+            little_endian,
+        } => {
+            let mut out = data.to_vec();
+            let bytes = if little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            };
+            out[offset..offset + 2].copy_from_slice(&bytes);
+            Some(out)
+        }
+        OrientationSlot::MissingTag => None,
+        OrientationSlot::NoExif { .. } if orientation == Orientation::NoTransforms => {
+            Some(data.to_vec())
+        }
+        OrientationSlot::NoExif { insert_at } => {
+            let segment = orientation_segment(value);
+            let mut out = Vec::with_capacity(data.len() + segment.len());
+            out.extend_from_slice(&data[..insert_at]);
+            out.extend_from_slice(&segment);
+            out.extend_from_slice(&data[insert_at..]);
+            Some(out)
+        }
+    }
+}
+
+/// 扫描 SOS 之前的标记段，找到 Exif APP1 并在其 IFD0 中定位 Orientation。
+fn locate_orientation(data: &[u8]) -> Option<OrientationSlot> {
+    if !is_jpeg(data) {
+        return None;
+    }
+    let mut pos = 2;
+    let mut insert_at = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        if marker == 0xFF {
+            // 填充字节
+            pos += 1;
+            continue;
+        }
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = usize::from(u16::from_be_bytes([data[pos + 2], data[pos + 3]]));
+        if len < 2 {
+            return None;
+        }
+        let segment = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xE1 && segment.starts_with(EXIF_HEADER) {
+            let tiff_start = pos + 4 + EXIF_HEADER.len();
+            return Some(match locate_in_tiff(&segment[EXIF_HEADER.len()..]) {
+                Some((offset, little_endian)) => OrientationSlot::Entry {
+                    offset: tiff_start + offset,
+                    little_endian,
+                },
+                None => OrientationSlot::MissingTag,
+            });
+        }
+        if marker == 0xE0 {
+            // 新段放在 JFIF APP0 之后，保持 APP0 紧跟 SOI。
+            insert_at = pos + 2 + len;
+        }
+        pos += 2 + len;
+    }
+    Some(OrientationSlot::NoExif { insert_at })
+}
+
+/// 返回 Orientation 值相对 TIFF 头的偏移与字节序。
+fn locate_in_tiff(tiff: &[u8]) -> Option<(usize, bool)> {
+    let little_endian = match tiff.get(0..4)? {
+        [0x49, 0x49, 42, 0] => true,
+        [0x4D, 0x4D, 0, 42] => false,
+        _ => return None,
+    };
+    let ifd = usize::try_from(read_u32(tiff, 4, little_endian)?).ok()?;
+    let count = usize::from(read_u16(tiff, ifd, little_endian)?);
+    for index in 0..count {
+        let entry = ifd + 2 + index * 12;
+        if read_u16(tiff, entry, little_endian)? == ORIENTATION_TAG {
+            // 类型必须是 SHORT，值左对齐存放在条目的最后 4 字节中。
+            return (read_u16(tiff, entry + 2, little_endian)? == 3)
+                .then_some((entry + 8, little_endian));
+        }
+    }
+    None
+}
+
+/// 只含 Orientation 一个条目的 Exif APP1 段（大端）。
+fn orientation_segment(value: u16) -> Vec<u8> {
+    let mut tiff = vec![0x4D, 0x4D, 0, 42, 0, 0, 0, 8, 0, 1];
+    tiff.extend_from_slice(&ORIENTATION_TAG.to_be_bytes());
+    tiff.extend_from_slice(&[0, 3, 0, 0, 0, 1]);
+    tiff.extend_from_slice(&value.to_be_bytes());
+    tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+
+    let len = (2 + EXIF_HEADER.len() + tiff.len()) as u16;
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&len.to_be_bytes());
+    segment.extend_from_slice(EXIF_HEADER);
+    segment.extend_from_slice(&tiff);
+    segment
+}
+
+fn read_u16(data: &[u8], at: usize, little_endian: bool) -> Option<u16> {
+    let bytes = [*data.get(at)?, *data.get(at + 1)?];
+    Some(if little_endian {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    })
+}
+
+fn read_u32(data: &[u8], at: usize, little_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(at..at + 4)?.try_into().ok()?;
+    Some(if little_endian {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOI: [u8; 2] = [0xFF, 0xD8];
+    /// SOS 之后的扫描数据不会被解析，用一个空 SOS 段加 EOI 代替。
+    const SCAN: [u8; 6] = [0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9];
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, marker];
+        out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn jfif_app0() -> Vec<u8> {
+        segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0")
+    }
+
+    /// IFD0 先放一个 ImageWidth 条目，再按需放 Orientation，验证按条目遍历。
+    fn exif_app1(little_endian: bool, orientation: Option<u16>) -> Vec<u8> {
+        let u16_bytes = |v: u16| {
+            if little_endian {
+                v.to_le_bytes()
+            } else {
+                v.to_be_bytes()
+            }
+        };
+        let u32_bytes = |v: u32| {
+            if little_endian {
+                v.to_le_bytes()
+            } else {
+                v.to_be_bytes()
+            }
+        };
+        let mut tiff = if little_endian {
+            vec![0x49, 0x49, 42, 0]
+        } else {
+            vec![0x4D, 0x4D, 0, 42]
+        };
+        tiff.extend_from_slice(&u32_bytes(8));
+        tiff.extend_from_slice(&u16_bytes(1 + u16::from(orientation.is_some())));
+        tiff.extend_from_slice(&u16_bytes(0x0100));
+        tiff.extend_from_slice(&u16_bytes(4));
+        tiff.extend_from_slice(&u32_bytes(1));
+        tiff.extend_from_slice(&u32_bytes(640));
+        if let Some(value) = orientation {
+            tiff.extend_from_slice(&u16_bytes(ORIENTATION_TAG));
+            tiff.extend_from_slice(&u16_bytes(3));
+            tiff.extend_from_slice(&u32_bytes(1));
+            tiff.extend_from_slice(&u16_bytes(value));
+            tiff.extend_from_slice(&[0, 0]);
+        }
+        tiff.extend_from_slice(&u32_bytes(0));
+
+        let mut payload = EXIF_HEADER.to_vec();
+        payload.extend_from_slice(&tiff);
+        segment(0xE1, &payload)
+    }
+
+    fn jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = SOI.to_vec();
+        for segment in segments {
+            out.extend_from_slice(segment);
+        }
+        out.extend_from_slice(&SCAN);
+        out
+    }
+
+    #[test]
+    fn reads_orientation_in_both_byte_orders() {
+        for little_endian in [true, false] {
+            let data = jpeg(&[jfif_app0(), exif_app1(little_endian, Some(6))]);
+            assert_eq!(jpeg_orientation(&data), Orientation::Rotate90);
+        }
+    }
+
+    #[test]
+    fn rewrites_existing_tag_in_place() {
+        for little_endian in [true, false] {
+            let data = jpeg(&[exif_app1(little_endian, Some(1))]);
+            let out = with_jpeg_orientation(&data, Orientation::Rotate270FlipH).unwrap();
+            assert_eq!(out.len(), data.len());
+            assert_eq!(jpeg_orientation(&out), Orientation::Rotate270FlipH);
+            let changed = (0..data.len())
+                .filter(|&i| data[i] != out[i])
+                .collect::<Vec<_>>();
+            assert_eq!(changed.len(), 1, "only the low byte of the value changes");
+        }
+    }
+
+    #[test]
+    fn inserts_app1_after_jfif_when_exif_is_missing() {
+        let app0 = jfif_app0();
+        let data = jpeg(std::slice::from_ref(&app0));
+        assert_eq!(jpeg_orientation(&data), Orientation::NoTransforms);
+
+        let out = with_jpeg_orientation(&data, Orientation::Rotate180).unwrap();
+        assert_eq!(&out[..2], &SOI);
+        assert_eq!(&out[2..2 + app0.len()], &app0[..]);
+        assert_eq!(&out[2 + app0.len()..4 + app0.len()], &[0xFF, 0xE1]);
+        assert!(out.ends_with(&SCAN));
+        assert_eq!(jpeg_orientation(&out), Orientation::Rotate180);
+    }
+
+    #[test]
+    fn inserts_app1_right_after_soi_without_jfif() {
+        let data = jpeg(&[]);
+        let out = with_jpeg_orientation(&data, Orientation::FlipVertical).unwrap();
+        assert_eq!(&out[2..4], &[0xFF, 0xE1]);
+        assert_eq!(jpeg_orientation(&out), Orientation::FlipVertical);
+    }
+
+    #[test]
+    fn identity_without_exif_keeps_bytes() {
+        let data = jpeg(&[jfif_app0()]);
+        assert_eq!(
+            with_jpeg_orientation(&data, Orientation::NoTransforms),
+            Some(data)
+        );
+    }
+
+    #[test]
+    fn exif_without_orientation_tag_is_not_rewritten() {
+        let data = jpeg(&[jfif_app0(), exif_app1(true, None)]);
+        assert_eq!(jpeg_orientation(&data), Orientation::NoTransforms);
+        assert_eq!(with_jpeg_orientation(&data, Orientation::Rotate90), None);
+    }
+
+    #[test]
+    fn rejects_non_jpeg_and_truncated_data() {
+        assert_eq!(
+            with_jpeg_orientation(b"\x89PNG\r\n", Orientation::Rotate90),
+            None
+        );
+        assert_eq!(jpeg_orientation(b"\x89PNG\r\n"), Orientation::NoTransforms);
+
+        let data = jpeg(&[exif_app1(false, Some(3))]);
+        let truncated = &data[..data.len() - SCAN.len() - 6];
+        assert_eq!(
+            with_jpeg_orientation(truncated, Orientation::Rotate90),
+            None
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use image::{DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// 滤镜参数，按降噪 → 高斯模糊 → USM 锐化的顺序应用，未设置的项跳过。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterParams {
+    /// 0–1：3×3 中值滤波结果与原图的混合比例。
+    pub denoise: Option<f32>,
+    /// 高斯模糊的 sigma（像素）。
+    pub blur: Option<f32>,
+    pub unsharp: Option<UnsharpParams>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsharpParams {
+    /// 锐化强度，1 表示叠加一倍的细节差值。
+    pub amount: f32,
+    /// 提取细节所用高斯模糊的 sigma（像素）。
+    pub radius: f32,
+    /// 差值不超过该值的像素不锐化，避免放大平坦区域的噪点。
+    #[serde(default)]
+    pub threshold: u8,
+}
+
+impl FilterParams {
+    pub fn is_empty(&self) -> bool {
+        self.denoise.is_none() && self.blur.is_none() && self.unsharp.is_none()
+    }
+}
+
+/// 逐行检查 `is_cancelled`，取消时返回 `AppError::cancelled()`。
+pub fn apply_filter(
+    img: DynamicImage,
+    params: &FilterParams,
+    is_cancelled: &mut dyn FnMut() -> bool,
+) -> Result<DynamicImage, AppError> {
+    let has_alpha = img.color().has_alpha();
+    let mut img = img;
+    if let Some(strength) = params.denoise.filter(|s| *s > 0.0) {
+        img = DynamicImage::ImageRgba8(denoise(&img.to_rgba8(), strength.min(1.0), is_cancelled)?);
+    }
+    if let Some(sigma) = params.blur.filter(|s| *s > 0.0) {
+        img = img.blur(sigma);
+    }
+    if let Some(unsharp) = &params.unsharp {
+        img = DynamicImage::ImageRgba8(unsharp_mask(&img.to_rgba8(), unsharp, is_cancelled)?);
+    }
+    Ok(if has_alpha || !img.color().has_alpha() {
+        img
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    })
+}
+
+/// 3×3 中值滤波后按 `strength` 与原图混合；中值能去掉孤立噪点而基本保留边缘。
+/// 直接读取上、中、下三行的原始缓冲区，边界像素按最近的行列补齐。
+fn denoise(
+    src: &RgbaImage,
+    strength: f32,
+    is_cancelled: &mut dyn FnMut() -> bool,
+) -> Result<RgbaImage, AppError> {
+    let (width, height) = src.dimensions();
+    let stride = width as usize * 4;
+    let raw = src.as_raw();
+    let row = |y: u32| &raw[y as usize * stride..(y as usize + 1) * stride];
+    let mut out = src.clone();
+    for (y, out_row) in out.chunks_exact_mut(stride).enumerate() {
+        if is_cancelled() {
+            return Err(AppError::cancelled());
+        }
+        let y = y as u32;
+        let rows = [
+            row(y.saturating_sub(1)),
+            row(y),
+            row((y + 1).min(height - 1)),
+        ];
+        for x in 0..width as usize {
+            let columns = [x.saturating_sub(1), x, (x + 1).min(width as usize - 1)];
+            for channel in 0..3 {
+                let mut window = [0u8; 9];
+                for (slot, (source, column)) in window.iter_mut().zip(
+                    rows.iter()
+                        .flat_map(|r| columns.iter().map(move |c| (r, c))),
+                ) {
+                    *slot = source[column * 4 + channel];
+                }
+                let (_, median, _) = window.select_nth_unstable(4);
+                let median = f32::from(*median);
+                let value = f32::from(out_row[x * 4 + channel]);
+                out_row[x * 4 + channel] = (value + (median - value) * strength).round() as u8;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 原图加上 `amount` 倍的（原图 − 模糊图）细节，差值低于阈值的像素保持不变。
+fn unsharp_mask(
+    src: &RgbaImage,
+    params: &UnsharpParams,
+    is_cancelled: &mut dyn FnMut() -> bool,
+) -> Result<RgbaImage, AppError> {
+    let blurred = image::imageops::blur(src, params.radius.max(0.1));
+    let threshold = i16::from(params.threshold);
+    let stride = src.width() as usize * 4;
+    let mut out = src.clone();
+    for (out_row, soft_row) in out
+        .chunks_exact_mut(stride)
+        .zip(blurred.chunks_exact(stride))
+    {
+        if is_cancelled() {
+            return Err(AppError::cancelled());
+        }
+        for (pixel, soft) in out_row.chunks_exact_mut(4).zip(soft_row.chunks_exact(4)) {
+            for channel in 0..3 {
+                let diff = i16::from(pixel[channel]) - i16::from(soft[channel]);
+                if diff.abs() > threshold {
+                    let value = f32::from(pixel[channel]) + f32::from(diff) * params.amount;
+                    pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use image::{Rgb, RgbImage, Rgba};
+
+    fn never() -> impl FnMut() -> bool {
+        || false
+    }
+
+    fn gray(width: u32, height: u32, f: impl Fn(u32, u32) -> u8) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let v = f(x, y);
+            Rgba([v, v, v, 200])
+        })
+    }
+
+    #[test]
+    fn denoise_removes_a_lone_impulse() {
+        let noisy = gray(5, 5, |x, y| if (x, y) == (2, 2) { 255 } else { 100 });
+        let out = denoise(&noisy, 1.0, &mut never()).unwrap();
+        assert!(out.pixels().all(|p| p.0 == [100, 100, 100, 200]));
+
+        // 强度 0.5 只向中值移动一半。
+        let half = denoise(&noisy, 0.5, &mut never()).unwrap();
+        assert_eq!(half.get_pixel(2, 2).0, [178, 178, 178, 200]);
+        assert_eq!(half.get_pixel(0, 0).0, [100, 100, 100, 200]);
+    }
+
+    #[test]
+    fn denoise_keeps_straight_edges_and_corner_impulses() {
+        let edge = gray(6, 4, |x, _| if x < 3 { 0 } else { 255 });
+        assert_eq!(denoise(&edge, 1.0, &mut never()).unwrap(), edge);
+
+        // 角上的噪点按补齐后的邻域计算，同样被去掉。
+        let corner = gray(4, 4, |x, y| if (x, y) == (3, 3) { 0 } else { 90 });
+        let out = denoise(&corner, 1.0, &mut never()).unwrap();
+        assert_eq!(out.get_pixel(3, 3).0, [90, 90, 90, 200]);
+    }
+
+    #[test]
+    fn unsharp_respects_the_threshold() {
+        // 左右相差 4 的缓坡：模糊差值不超过 4。
+        let soft_step = gray(8, 4, |x, _| if x < 4 { 100 } else { 104 });
+        let params = |threshold| UnsharpParams {
+            amount: 1.0,
+            radius: 1.0,
+            threshold,
+        };
+        let kept = unsharp_mask(&soft_step, &params(4), &mut never()).unwrap();
+        assert_eq!(kept, soft_step);
+        let sharpened = unsharp_mask(&soft_step, &params(0), &mut never()).unwrap();
+        assert_ne!(sharpened, soft_step);
+        assert!(sharpened.get_pixel(3, 0)[0] < 100);
+        assert!(sharpened.get_pixel(4, 0)[0] > 104);
+
+        // 明显的边缘超过阈值，照常锐化；平坦区域不变。
+        let hard_step = gray(8, 4, |x, _| if x < 4 { 50 } else { 200 });
+        let out = unsharp_mask(&hard_step, &params(4), &mut never()).unwrap();
+        assert!(out.get_pixel(3, 0)[0] < 50);
+        assert!(out.get_pixel(4, 0)[0] > 200);
+        assert_eq!(out.get_pixel(4, 0)[3], 200);
+    }
+
+    #[test]
+    fn apply_filter_keeps_rgb_and_stops_when_cancelled() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([10, 20, 30])));
+        let params = FilterParams {
+            denoise: Some(1.0),
+            unsharp: Some(UnsharpParams {
+                amount: 0.5,
+                radius: 1.0,
+                threshold: 0,
+            }),
+            ..Default::default()
+        };
+        let out = apply_filter(img.clone(), &params, &mut never()).unwrap();
+        assert_eq!(out.color(), image::ColorType::Rgb8);
+        assert_eq!(out.to_rgb8(), img.to_rgb8());
+
+        let mut checks = 0;
+        let err = apply_filter(img, &params, &mut || {
+            checks += 1;
+            checks > 2
+        })
+        .unwrap_err();
+        assert_eq!(err.code, ErrorCode::Cancelled);
+        assert_eq!(checks, 3);
+    }
+}
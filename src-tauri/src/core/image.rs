@@ -1,8 +1,16 @@
 use image::codecs::png::{CompressionType, FilterType};
-use image::{DynamicImage, ExtendedColorType, GenericImageView, ImageEncoder, ImageFormat};
-use std::path::{Path, PathBuf};
-
-use crate::{CompressMode, CropOptions, CropRegion, ProcessOptions};
+use image::metadata::Orientation;
+use image::{
+    DynamicImage, ExtendedColorType, GenericImageView, ImageDecoder, ImageEncoder, ImageFormat,
+    ImageReader, Rgba, RgbaImage,
+};
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::core::adjust::apply_adjust;
+use crate::core::transform::parse_color;
+use crate::error::{AppError, ErrorCode};
+use crate::{CompressMode, CropOptions, CropRegion, ProcessOptions, ResizeMode};
 
 pub struct ImageMetadata {
     pub size_bytes: u64,
@@ -11,19 +19,21 @@ pub struct ImageMetadata {
     pub height: Option<u32>,
 }
 
-pub fn get_file_info(path: &str) -> Result<ImageMetadata, String> {
+pub fn get_file_info(path: &str) -> Result<ImageMetadata, AppError> {
     let path_buf = Path::new(path);
     if !path_buf.exists() {
-        return Err("File not found".into());
+        return Err(AppError::new(ErrorCode::FileNotFound, "File not found").with_path(path));
     }
 
-    let size_bytes = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let size_bytes = std::fs::metadata(path)
+        .map_err(|e| AppError::io(e, path))?
+        .len();
     let ext = path_buf
         .extension()
         .and_then(|e| e.to_str())
         .map(|s| s.to_string());
 
-    let (format, width, height) = match image::open(path) {
+    let (format, width, height) = match load_image(path) {
         Ok(img) => {
             let (w, h) = img.dimensions();
             let fmt = ImageFormat::from_path(path_buf)
@@ -50,11 +60,28 @@ pub fn get_file_info(path: &str) -> Result<ImageMetadata, String> {
     })
 }
 
-pub fn load_image(path: &str) -> Result<DynamicImage, String> {
-    image::open(path).map_err(|e| e.to_string())
+/// 按 EXIF Orientation 摆正后的图片。
+pub fn load_image(path: &str) -> Result<DynamicImage, AppError> {
+    decode_image(path, true)
+}
+
+/// 解码图片；`auto_orient` 时按 EXIF Orientation 摆正像素，因为重新编码不会保留 EXIF。
+pub fn decode_image(path: &str, auto_orient: bool) -> Result<DynamicImage, AppError> {
+    let mut decoder = ImageReader::open(path)
+        .map_err(|e| AppError::io(e, path))?
+        .into_decoder()
+        .map_err(|e| AppError::image(e, path))?;
+    let orientation = if auto_orient {
+        decoder.orientation().unwrap_or(Orientation::NoTransforms)
+    } else {
+        Orientation::NoTransforms
+    };
+    let mut img = DynamicImage::from_decoder(decoder).map_err(|e| AppError::image(e, path))?;
+    img.apply_orientation(orientation);
+    Ok(img)
 }
 
-pub fn apply_crop(img: DynamicImage, crop_region: &CropRegion) -> Result<DynamicImage, String> {
+pub fn apply_crop(img: DynamicImage, crop_region: &CropRegion) -> Result<DynamicImage, AppError> {
     let (w, h) = img.dimensions();
     validate_crop_bounds(w, h, crop_region)?;
 
@@ -67,47 +94,203 @@ pub fn apply_crop(img: DynamicImage, crop_region: &CropRegion) -> Result<Dynamic
 }
 
 /// 统一裁剪区域越界校验，避免重复逻辑和潜在 u32 溢出。
-fn validate_crop_bounds(w: u32, h: u32, crop_region: &CropRegion) -> Result<(), String> {
+fn validate_crop_bounds(w: u32, h: u32, crop_region: &CropRegion) -> Result<(), AppError> {
     let right = crop_region
         .x
         .checked_add(crop_region.width)
-        .ok_or_else(|| "Crop region overflow on x + width".to_string())?;
+        .ok_or_else(|| crop_error("Crop region overflow on x + width".into()))?;
     let bottom = crop_region
         .y
         .checked_add(crop_region.height)
-        .ok_or_else(|| "Crop region overflow on y + height".to_string())?;
+        .ok_or_else(|| crop_error("Crop region overflow on y + height".into()))?;
 
     if right > w || bottom > h {
-        return Err(format!(
+        return Err(crop_error(format!(
             "Crop region out of bounds: image {}x{}, region x={} y={} {}x{}",
             w, h, crop_region.x, crop_region.y, crop_region.width, crop_region.height
-        ));
+        )));
     }
 
     Ok(())
 }
 
+fn crop_error(message: String) -> AppError {
+    AppError::new(ErrorCode::CropOutOfBounds, message)
+}
+
 pub fn apply_resize(img: DynamicImage, options: Option<&ProcessOptions>) -> DynamicImage {
-    let (orig_w, orig_h) = img.dimensions();
-    let (target_w, target_h) = resolve_target_dimensions(&img, options);
-    if target_w != orig_w || target_h != orig_h {
-        return img.resize(target_w, target_h, image::imageops::FilterType::Lanczos3);
+    match options {
+        Some(opts) => resize_image(img, &ResizeSpec::from_options(opts)),
+        None => img,
+    }
+}
+
+/// 一次缩放的完整参数；宽高的含义随 `mode` 而定，未给出尺寸时保持原图。
+pub struct ResizeSpec {
+    pub mode: ResizeMode,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub edge: Option<u32>,
+    pub percent: Option<f32>,
+    pub no_upscale: bool,
+    pub background: Rgba<u8>,
+    pub filter: image::imageops::FilterType,
+}
+
+impl ResizeSpec {
+    pub fn from_options(options: &ProcessOptions) -> Self {
+        Self {
+            mode: options.resize_mode.unwrap_or_default(),
+            width: options.width,
+            height: options.height,
+            edge: options.resize_edge,
+            percent: options.resize_percent,
+            no_upscale: options.no_upscale.unwrap_or(false),
+            background: options
+                .resize_background
+                .as_deref()
+                .and_then(parse_color)
+                .unwrap_or(Rgba([0, 0, 0, 0])),
+            filter: options.resize_filter.unwrap_or_default().into(),
+        }
     }
-    img
 }
 
-/// 根据可选参数推导缩放目标尺寸；未指定则保留原图尺寸。
-fn resolve_target_dimensions(img: &DynamicImage, options: Option<&ProcessOptions>) -> (u32, u32) {
+/// 缩放后的尺寸与最终画布尺寸；两者不同时 Cover 居中裁剪、Pad 居中补边。
+struct ResizePlan {
+    scaled: (u32, u32),
+    canvas: (u32, u32),
+}
+
+pub fn resize_image(img: DynamicImage, spec: &ResizeSpec) -> DynamicImage {
     let (orig_w, orig_h) = img.dimensions();
-    let target_w = options
-        .and_then(|opts| opts.width)
-        .filter(|w| *w > 0)
-        .unwrap_or(orig_w);
-    let target_h = options
-        .and_then(|opts| opts.height)
-        .filter(|h| *h > 0)
-        .unwrap_or(orig_h);
-    (target_w, target_h)
+    let plan = plan_resize(orig_w, orig_h, spec);
+    let (scaled_w, scaled_h) = plan.scaled;
+    let img = if plan.scaled != (orig_w, orig_h) {
+        img.resize_exact(scaled_w, scaled_h, spec.filter)
+    } else {
+        img
+    };
+
+    let (canvas_w, canvas_h) = plan.canvas;
+    if plan.canvas == plan.scaled {
+        img
+    } else if canvas_w <= scaled_w && canvas_h <= scaled_h {
+        img.crop_imm(
+            (scaled_w - canvas_w) / 2,
+            (scaled_h - canvas_h) / 2,
+            canvas_w,
+            canvas_h,
+        )
+    } else {
+        let mut canvas = RgbaImage::from_pixel(canvas_w, canvas_h, spec.background);
+        image::imageops::overlay(
+            &mut canvas,
+            &img.to_rgba8(),
+            i64::from(canvas_w.saturating_sub(scaled_w) / 2),
+            i64::from(canvas_h.saturating_sub(scaled_h) / 2),
+        );
+        DynamicImage::ImageRgba8(canvas)
+    }
+}
+
+fn plan_resize(orig_w: u32, orig_h: u32, spec: &ResizeSpec) -> ResizePlan {
+    let (ow, oh) = (f64::from(orig_w), f64::from(orig_h));
+    let box_w = spec.width.filter(|w| *w > 0).map(f64::from);
+    let box_h = spec.height.filter(|h| *h > 0).map(f64::from);
+    let scaled = |scale: f64| {
+        let scale = if spec.no_upscale {
+            scale.min(1.0)
+        } else {
+            scale
+        };
+        (round_dimension(ow * scale), round_dimension(oh * scale))
+    };
+    let same = |size: (u32, u32)| ResizePlan {
+        scaled: size,
+        canvas: size,
+    };
+    // 缩入宽高框的比例；只给一边时另一边不限。
+    let fit_scale = match (box_w, box_h) {
+        (Some(w), Some(h)) => (w / ow).min(h / oh),
+        (Some(w), None) => w / ow,
+        (None, Some(h)) => h / oh,
+        (None, None) => 1.0,
+    };
+
+    match spec.mode {
+        ResizeMode::Exact => {
+            let (w, h) = match (box_w, box_h) {
+                (Some(w), Some(h)) => (w, h),
+                (Some(w), None) => (w, oh * w / ow),
+                (None, Some(h)) => (ow * h / oh, h),
+                (None, None) => (ow, oh),
+            };
+            // 不放大时按原比例整体缩回，保持要求的宽高比。
+            let shrink = if spec.no_upscale {
+                (ow / w).min(oh / h).min(1.0)
+            } else {
+                1.0
+            };
+            same((round_dimension(w * shrink), round_dimension(h * shrink)))
+        }
+        ResizeMode::Fit => same(scaled(fit_scale)),
+        ResizeMode::Pad => {
+            let size = scaled(fit_scale);
+            match (
+                spec.width.filter(|w| *w > 0),
+                spec.height.filter(|h| *h > 0),
+            ) {
+                (Some(w), Some(h)) => ResizePlan {
+                    scaled: size,
+                    canvas: (w, h),
+                },
+                _ => same(size),
+            }
+        }
+        ResizeMode::Cover => match (box_w, box_h) {
+            (Some(w), Some(h)) => {
+                let scale = (w / ow).max(h / oh);
+                if spec.no_upscale && scale > 1.0 {
+                    // 不放大时在原图上裁出同宽高比的最大区域。
+                    ResizePlan {
+                        scaled: (orig_w, orig_h),
+                        canvas: (
+                            round_dimension(w / scale).min(orig_w),
+                            round_dimension(h / scale).min(orig_h),
+                        ),
+                    }
+                } else {
+                    let (w, h) = (round_dimension(w), round_dimension(h));
+                    let (scaled_w, scaled_h) = scaled(scale);
+                    ResizePlan {
+                        scaled: (scaled_w.max(w), scaled_h.max(h)),
+                        canvas: (w, h),
+                    }
+                }
+            }
+            _ => same(scaled(fit_scale)),
+        },
+        ResizeMode::LongestEdge | ResizeMode::ShortestEdge => match spec.edge {
+            Some(edge) if edge > 0 => {
+                let reference = if spec.mode == ResizeMode::LongestEdge {
+                    ow.max(oh)
+                } else {
+                    ow.min(oh)
+                };
+                same(scaled(f64::from(edge) / reference))
+            }
+            _ => same((orig_w, orig_h)),
+        },
+        ResizeMode::Percentage => match spec.percent {
+            Some(percent) if percent > 0.0 => same(scaled(f64::from(percent) / 100.0)),
+            _ => same((orig_w, orig_h)),
+        },
+    }
+}
+
+fn round_dimension(value: f64) -> u32 {
+    value.round().clamp(1.0, f64::from(u32::MAX)) as u32
 }
 
 pub fn resolve_output_format(path: &str, options: Option<&ProcessOptions>) -> String {
@@ -133,7 +316,7 @@ pub fn save_image_with_format(
     format: &str,
     mode: &CompressMode,
     quality: Option<u8>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     save_image_with_format_progress(img, output_path, format, mode, quality, |_| {})
 }
 
@@ -144,22 +327,53 @@ pub fn save_image_with_format_progress<F>(
     mode: &CompressMode,
     quality: Option<u8>,
     mut progress_callback: F,
-) -> Result<(), String>
+) -> Result<(), AppError>
+where
+    F: FnMut(f32),
+{
+    let buf = encode_image(img, format, mode, quality, |p| progress_callback(p * 0.9))
+        .map_err(|e| e.with_path(output_path))?;
+    std::fs::write(output_path, &buf).map_err(|e| AppError::io(e, output_path))?;
+    progress_callback(100.0);
+    Ok(())
+}
+
+/// 按目标格式在内存中编码，不写盘；png/jpeg/webp 之外的格式交给 `image` 按扩展名编码。
+pub fn encode_image<F>(
+    img: &DynamicImage,
+    format: &str,
+    mode: &CompressMode,
+    quality: Option<u8>,
+    mut progress_callback: F,
+) -> Result<Vec<u8>, AppError>
 where
     F: FnMut(f32),
 {
     progress_callback(0.0);
-    match format {
-        "png" => compress_png(img, output_path, mode, &mut progress_callback),
-        "jpg" | "jpeg" => compress_jpeg(img, output_path, mode, quality, &mut progress_callback),
-        "webp" => compress_webp(img, output_path, mode, quality, &mut progress_callback),
+    let buf = match format {
+        "png" => encode_png(img, mode, &mut progress_callback)?,
+        "jpg" | "jpeg" => encode_jpeg(img, mode, quality, &mut progress_callback)?,
+        "webp" => encode_webp(img, mode, quality, &mut progress_callback)?,
         _ => {
+            let image_format = ImageFormat::from_extension(format).ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::UnsupportedImage,
+                    format!("unsupported output format: {format}"),
+                )
+            })?;
             progress_callback(20.0);
-            img.save(output_path).map_err(|e| e.to_string())?;
-            progress_callback(100.0);
-            Ok(())
+            let mut buf = Vec::new();
+            img.write_to(&mut Cursor::new(&mut buf), image_format)
+                .map_err(encode_error)?;
+            buf
         }
-    }
+    };
+    progress_callback(100.0);
+    Ok(buf)
+}
+
+fn encode_error(err: image::ImageError) -> AppError {
+    AppError::new(ErrorCode::EncodeFailed, err.to_string())
 }
 
 pub fn compress_image<F>(
@@ -169,12 +383,13 @@ pub fn compress_image<F>(
     crop_region: Option<&CropRegion>,
     options: Option<&ProcessOptions>,
     mut progress_callback: F,
-) -> Result<(), String>
+) -> Result<(), AppError>
 where
     F: FnMut(u8),
 {
     // 1) 读取图片
-    let mut img = load_image(path)?;
+    let auto_orient = options.and_then(|opts| opts.auto_orient).unwrap_or(true);
+    let mut img = decode_image(path, auto_orient)?;
     progress_callback(10); // 读取完成
 
     // 2) 可选裁剪
@@ -185,20 +400,25 @@ where
 
     // 3) 可选缩放
     let (orig_w, orig_h) = img.dimensions();
-    let (target_w, target_h) = resolve_target_dimensions(&img, options);
     img = apply_resize(img, options);
+    let (target_w, target_h) = img.dimensions();
     progress_callback(60); // resize完成
 
-    // 4) 编码格式选择（优先 options.format，否则沿用原扩展名）
+    // 4) 可选颜色调整
+    if let Some(adjust) = options.and_then(|opts| opts.adjust.as_ref()) {
+        img = apply_adjust(img, adjust);
+    }
+
+    // 5) 编码格式选择（优先 options.format，否则沿用原扩展名）
     let format = resolve_output_format(path, options);
 
-    // 5) 编码输出
+    // 6) 编码输出
     let quality = options.and_then(|opts| opts.quality);
     save_image_with_format(&img, output_path, &format, mode, quality)?;
     progress_callback(90); // 编码完成
 
 
-    // 6) 无任何显式处理时，若输出更大则回退到原图，避免体积倒挂。
+    // 7) 无任何显式处理时，若输出更大则回退到原图，避免体积倒挂。
     let has_explicit_processing = options.is_some_and(|opts| {
         opts.quality.is_some()
             || opts.format.as_deref().is_some_and(|f| {
@@ -207,6 +427,10 @@ where
             })
             || opts.width.unwrap_or(0) > 0
             || opts.height.unwrap_or(0) > 0
+            || opts
+                .adjust
+                .as_ref()
+                .is_some_and(|adjust| !adjust.is_identity())
     });
 
     if crop_region.is_none()
@@ -215,12 +439,16 @@ where
         && !has_explicit_processing
         && Path::new(path) != Path::new(output_path)
     {
-        let input_size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
-        let output_size = std::fs::metadata(output_path).map_err(|e| e.to_string())?.len();
+        let input_size = std::fs::metadata(path)
+            .map_err(|e| AppError::io(e, path))?
+            .len();
+        let output_size = std::fs::metadata(output_path)
+            .map_err(|e| AppError::io(e, output_path))?
+            .len();
 
         if output_size >= input_size {
             std::fs::remove_file(output_path).ok();
-            std::fs::copy(path, output_path).map_err(|e| e.to_string())?;
+            std::fs::copy(path, output_path).map_err(|e| AppError::io(e, output_path))?;
         }
     }
     progress_callback(100); // 完成
@@ -228,12 +456,11 @@ where
 }
 
 
-fn compress_png(
+fn encode_png(
     img: &image::DynamicImage,
-    output_path: &str,
     mode: &CompressMode,
     progress_callback: &mut dyn FnMut(f32),
-) -> Result<(), String> {
+) -> Result<Vec<u8>, AppError> {
     progress_callback(5.0);
     let mut buf = Vec::new();
     let (w, h) = img.dimensions();
@@ -246,7 +473,7 @@ fn compress_png(
         );
         png_encoder
             .write_image(raw.as_raw(), w, h, ExtendedColorType::Rgba8)
-            .map_err(|e: image::ImageError| e.to_string())?;
+            .map_err(encode_error)?;
     }
     progress_callback(35.0);
 
@@ -254,38 +481,32 @@ fn compress_png(
         CompressMode::Lossless => {
             let mut opt = oxipng::Options::from_preset(3);
             opt.optimize_alpha = true;
-            let in_file = std::env::temp_dir().join("cruncher_png_input.png");
-            std::fs::write(&in_file, &buf).map_err(|e| e.to_string())?;
             progress_callback(55.0);
-            let in_file_oxi = oxipng::InFile::Path(in_file.clone());
-            let out_file = oxipng::OutFile::from_path(PathBuf::from(output_path));
-            progress_callback(75.0);
-            oxipng::optimize(&in_file_oxi, &out_file, &opt).map_err(|e| e.to_string())?;
-            let _ = std::fs::remove_file(in_file);
-            progress_callback(100.0);
-        }
-        CompressMode::VisuallyLossless => {
-            std::fs::write(output_path, &buf).map_err(|e| e.to_string())?;
-            progress_callback(100.0);
+            let optimized = oxipng::optimize_from_memory(&buf, &opt)
+                .map_err(|e| AppError::new(ErrorCode::EncodeFailed, e.to_string()))?;
+            Ok(optimized)
         }
+        CompressMode::VisuallyLossless => Ok(buf),
     }
-    Ok(())
 }
 
-fn compress_jpeg(
+fn encode_jpeg(
     img: &image::DynamicImage,
-    output_path: &str,
     mode: &CompressMode,
     quality: Option<u8>,
     progress_callback: &mut dyn FnMut(f32),
-) -> Result<(), String> {
+) -> Result<Vec<u8>, AppError> {
     progress_callback(5.0);
     let quality = quality.unwrap_or(match mode {
         CompressMode::Lossless => 100,
         CompressMode::VisuallyLossless => 96,
     });
     let quality = quality.clamp(1, 100);
-    let rgb = img.to_rgb8();
+    let rgb = if img.color().has_alpha() {
+        flatten_onto_white(img)
+    } else {
+        img.to_rgb8()
+    };
     progress_callback(20.0);
     let (w, h) = rgb.dimensions();
     let mut buf = Vec::new();
@@ -294,39 +515,58 @@ fn compress_jpeg(
             image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
         encoder
             .encode(rgb.as_raw(), w, h, ExtendedColorType::Rgb8)
-            .map_err(|e| e.to_string())?;
+            .map_err(encode_error)?;
     }
     progress_callback(75.0);
-    // Encoder must be dropped so any buffered data is flushed to buf before we write.
+    // Encoder must be dropped so any buffered data is flushed to buf before we return it.
     if buf.is_empty() {
-        return Err("JPEG encoding produced empty output".into());
+        return Err(AppError::new(
+            ErrorCode::EncodeFailed,
+            "JPEG encoding produced empty output",
+        ));
     }
-    std::fs::write(output_path, &buf).map_err(|e| e.to_string())?;
-    progress_callback(100.0);
-    Ok(())
+    Ok(buf)
+}
+
+/// JPEG 没有透明通道：透明补边、旋转空角等区域按 alpha 混合到白底上，而不是直接丢弃 alpha 变成黑色。
+fn flatten_onto_white(img: &DynamicImage) -> image::RgbImage {
+    let rgba = img.to_rgba8();
+    image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *rgba.get_pixel(x, y);
+        let alpha = u16::from(a);
+        let blend = |c: u8| ((u16::from(c) * alpha + 255 * (255 - alpha) + 127) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
 }
 
-fn compress_webp(
+fn encode_webp(
     img: &image::DynamicImage,
-    output_path: &str,
     mode: &CompressMode,
     quality: Option<u8>,
     progress_callback: &mut dyn FnMut(f32),
-) -> Result<(), String> {
+) -> Result<Vec<u8>, AppError> {
     progress_callback(5.0);
-    let rgb = img.to_rgb8();
+    // 带透明通道的图片（如透明补边）保留 alpha。
+    let has_alpha = img.color().has_alpha();
+    let (w, h) = img.dimensions();
+    let raw = if has_alpha {
+        img.to_rgba8().into_raw()
+    } else {
+        img.to_rgb8().into_raw()
+    };
     progress_callback(20.0);
-    let (w, h) = rgb.dimensions();
-    let encoder = webp::Encoder::from_rgb(rgb.as_raw(), w, h);
+    let encoder = if has_alpha {
+        webp::Encoder::from_rgba(&raw, w, h)
+    } else {
+        webp::Encoder::from_rgb(&raw, w, h)
+    };
     let quality = quality.unwrap_or(96).clamp(1, 100) as f32;
     let buf = match mode {
         CompressMode::Lossless => encoder.encode_lossless(),
         CompressMode::VisuallyLossless => encoder.encode(quality),
     };
     progress_callback(80.0);
-    std::fs::write(output_path, &*buf).map_err(|e| e.to_string())?;
-    progress_callback(100.0);
-    Ok(())
+    Ok(buf.to_vec())
 }
 
 
@@ -335,15 +575,15 @@ pub fn perform_crop(
     input_path: &str,
     output_path: &str,
     options: &CropOptions,
-) -> Result<(), String> {
-    let img = load_image(input_path).map_err(|e| format!("Failed to open image: {e}"))?;
+) -> Result<(), AppError> {
+    let img = load_image(input_path)?;
     let crop_region = CropRegion {
         x: options.x,
         y: options.y,
         width: options.width,
         height: options.height,
     };
-    let cropped = apply_crop(img, &crop_region).map_err(|e| format!("Crop failed: {e}"))?;
+    let cropped = apply_crop(img, &crop_region)?;
 
     // 是否圆形裁剪
     if options.circular.unwrap_or(false) {
@@ -364,7 +604,7 @@ pub fn perform_crop(
         }
 
         rgba.save(output_path)
-            .map_err(|e| format!("Save failed: {e}"))?;
+            .map_err(|e| AppError::image(e, output_path))?;
 
         return Ok(());
     }
@@ -381,7 +621,230 @@ pub fn perform_crop(
 
     cropped
         .save_with_format(output_path, format)
-        .map_err(|e| format!("Save failed: {e}"))?;
+        .map_err(|e| AppError::image(e, output_path))?;
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(mode: ResizeMode) -> ResizeSpec {
+        ResizeSpec {
+            mode,
+            width: None,
+            height: None,
+            edge: None,
+            percent: None,
+            no_upscale: false,
+            background: Rgba([0, 0, 0, 0]),
+            filter: image::imageops::FilterType::Lanczos3,
+        }
+    }
+
+    fn boxed(mode: ResizeMode, width: Option<u32>, height: Option<u32>) -> ResizeSpec {
+        ResizeSpec {
+            width,
+            height,
+            ..spec(mode)
+        }
+    }
+
+    fn no_upscale(spec: ResizeSpec) -> ResizeSpec {
+        ResizeSpec {
+            no_upscale: true,
+            ..spec
+        }
+    }
+
+    fn plan(size: (u32, u32), spec: &ResizeSpec) -> ((u32, u32), (u32, u32)) {
+        let plan = plan_resize(size.0, size.1, spec);
+        (plan.scaled, plan.canvas)
+    }
+
+    const PHOTO: (u32, u32) = (4000, 3000);
+    const SMALL: (u32, u32) = (400, 300);
+
+    #[test]
+    fn fit_width_only_keeps_aspect() {
+        for mode in [ResizeMode::Fit, ResizeMode::Exact] {
+            let spec = boxed(mode, Some(800), None);
+            assert_eq!(plan(PHOTO, &spec), ((800, 600), (800, 600)), "{mode:?}");
+            assert_eq!(
+                plan(PHOTO, &no_upscale(spec)),
+                ((800, 600), (800, 600)),
+                "{mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fit_uses_the_tighter_side() {
+        let spec = boxed(ResizeMode::Fit, Some(800), Some(800));
+        assert_eq!(plan(PHOTO, &spec), ((800, 600), (800, 600)));
+        assert_eq!(plan((3000, 4000), &spec), ((600, 800), (600, 800)));
+    }
+
+    #[test]
+    fn fit_no_upscale() {
+        let spec = boxed(ResizeMode::Fit, Some(800), None);
+        assert_eq!(plan(SMALL, &spec), ((800, 600), (800, 600)));
+        assert_eq!(plan(SMALL, &no_upscale(spec)), (SMALL, SMALL));
+    }
+
+    #[test]
+    fn exact_stretches() {
+        let spec = boxed(ResizeMode::Exact, Some(800), Some(800));
+        assert_eq!(plan(PHOTO, &spec), ((800, 800), (800, 800)));
+        assert_eq!(plan(SMALL, &spec), ((800, 800), (800, 800)));
+    }
+
+    #[test]
+    fn exact_no_upscale_shrinks_the_box_keeping_its_ratio() {
+        let spec = no_upscale(boxed(ResizeMode::Exact, Some(800), Some(800)));
+        assert_eq!(plan(PHOTO, &spec), ((800, 800), (800, 800)));
+        assert_eq!(plan(SMALL, &spec), ((300, 300), (300, 300)));
+    }
+
+    #[test]
+    fn cover_fills_then_crops() {
+        let spec = boxed(ResizeMode::Cover, Some(800), Some(800));
+        assert_eq!(plan(PHOTO, &spec), ((1067, 800), (800, 800)));
+        assert_eq!(plan(SMALL, &spec), ((1067, 800), (800, 800)));
+    }
+
+    #[test]
+    fn cover_no_upscale_crops_the_largest_matching_region() {
+        let spec = no_upscale(boxed(ResizeMode::Cover, Some(800), Some(800)));
+        assert_eq!(plan(PHOTO, &spec), ((1067, 800), (800, 800)));
+        assert_eq!(plan(SMALL, &spec), (SMALL, (300, 300)));
+    }
+
+    #[test]
+    fn cover_with_one_side_behaves_like_fit() {
+        let spec = boxed(ResizeMode::Cover, Some(800), None);
+        assert_eq!(plan(PHOTO, &spec), ((800, 600), (800, 600)));
+    }
+
+    #[test]
+    fn pad_fits_inside_the_canvas() {
+        let spec = boxed(ResizeMode::Pad, Some(800), Some(800));
+        assert_eq!(plan(PHOTO, &spec), ((800, 600), (800, 800)));
+        assert_eq!(plan(SMALL, &spec), ((800, 600), (800, 800)));
+        assert_eq!(plan(SMALL, &no_upscale(spec)), (SMALL, (800, 800)));
+
+        let one_side = boxed(ResizeMode::Pad, Some(800), None);
+        assert_eq!(plan(PHOTO, &one_side), ((800, 600), (800, 600)));
+    }
+
+    #[test]
+    fn edge_modes() {
+        let longest = ResizeSpec {
+            edge: Some(1000),
+            ..spec(ResizeMode::LongestEdge)
+        };
+        let shortest = ResizeSpec {
+            edge: Some(1000),
+            ..spec(ResizeMode::ShortestEdge)
+        };
+        assert_eq!(plan(PHOTO, &longest), ((1000, 750), (1000, 750)));
+        assert_eq!(plan(PHOTO, &shortest), ((1333, 1000), (1333, 1000)));
+        assert_eq!(plan(SMALL, &longest), ((1000, 750), (1000, 750)));
+        assert_eq!(plan(SMALL, &no_upscale(longest)), (SMALL, SMALL));
+        assert_eq!(plan(SMALL, &no_upscale(shortest)), (SMALL, SMALL));
+    }
+
+    #[test]
+    fn percentage() {
+        let half = ResizeSpec {
+            percent: Some(50.0),
+            ..spec(ResizeMode::Percentage)
+        };
+        let double = ResizeSpec {
+            percent: Some(200.0),
+            ..spec(ResizeMode::Percentage)
+        };
+        assert_eq!(plan(PHOTO, &half), ((2000, 1500), (2000, 1500)));
+        assert_eq!(plan(SMALL, &double), ((800, 600), (800, 600)));
+        assert_eq!(plan(SMALL, &no_upscale(double)), (SMALL, SMALL));
+    }
+
+    #[test]
+    fn missing_size_keeps_the_original() {
+        for mode in [
+            ResizeMode::Exact,
+            ResizeMode::Fit,
+            ResizeMode::Cover,
+            ResizeMode::Pad,
+            ResizeMode::LongestEdge,
+            ResizeMode::ShortestEdge,
+            ResizeMode::Percentage,
+        ] {
+            assert_eq!(plan(PHOTO, &spec(mode)), (PHOTO, PHOTO), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn jpeg_flattens_transparency_onto_white() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        }));
+        let flat = flatten_onto_white(&img);
+        assert_eq!(flat.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(flat.get_pixel(1, 0).0, [0, 0, 0]);
+
+        let jpeg = encode_image(&img, "jpeg", &CompressMode::Lossless, None, |_| {}).unwrap();
+        let decoded = image::load_from_memory(&jpeg).unwrap().to_rgb8();
+        assert!(decoded.get_pixel(0, 0).0.iter().all(|c| *c > 200));
+    }
+
+    #[test]
+    fn resize_image_crops_and_pads_to_the_canvas() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 30, Rgba([255, 0, 0, 255])));
+
+        let cover = resize_image(img.clone(), &boxed(ResizeMode::Cover, Some(20), Some(20)));
+        assert_eq!(cover.dimensions(), (20, 20));
+        assert_eq!(cover.to_rgba8().get_pixel(0, 0)[3], 255);
+
+        let pad = resize_image(img, &boxed(ResizeMode::Pad, Some(20), Some(20)));
+        assert_eq!(pad.dimensions(), (20, 20));
+        let pad = pad.to_rgba8();
+        assert_eq!(pad.get_pixel(10, 0)[3], 0);
+        assert_eq!(pad.get_pixel(10, 10)[3], 255);
+        assert_eq!(pad.get_pixel(10, 19)[3], 0);
+    }
+
+    #[test]
+    fn compress_image_applies_colour_adjustments() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("cruncher-adjust-in-{}.png", std::process::id()));
+        let output = dir.join(format!("cruncher-adjust-out-{}.png", std::process::id()));
+        image::RgbImage::from_pixel(4, 4, image::Rgb([200, 50, 10]))
+            .save(&input)
+            .unwrap();
+        let options = ProcessOptions {
+            adjust: Some(crate::core::adjust::AdjustParams {
+                invert: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        compress_image(
+            &input.to_string_lossy(),
+            &output.to_string_lossy(),
+            &CompressMode::Lossless,
+            None,
+            Some(&options),
+            |_| {},
+        )
+        .unwrap();
+        let pixel = *image::open(&output).unwrap().to_rgb8().get_pixel(0, 0);
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+        assert_eq!(pixel, image::Rgb([55, 205, 245]));
+    }
+}
@@ -1,2 +1,7 @@
+pub mod adjust;
+pub mod exif;
+pub mod filter;
 pub mod image;
+pub mod transform;
 pub mod video;
+pub mod watermark;
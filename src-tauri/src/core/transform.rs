@@ -0,0 +1,399 @@
+use image::metadata::Orientation;
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// 角度误差小于该值时按直角处理，走无插值的精确旋转。
+const RIGHT_ANGLE_EPSILON: f32 = 0.01;
+
+/// 顺时针旋转 `degrees` 度。直角精确旋转；其它角度双线性插值，画布扩大到能容纳整幅图，空出的角落用 `background` 填充。
+pub fn rotate(img: DynamicImage, degrees: f32, background: Rgba<u8>) -> DynamicImage {
+    if let Some(orientation) = right_angle_orientation(degrees) {
+        let mut img = img;
+        img.apply_orientation(orientation);
+        return img;
+    }
+
+    let src = img.to_rgba8();
+    let (w, h) = src.dimensions();
+    let (sin, cos) = f64::from(degrees).to_radians().sin_cos();
+    let new_w = (f64::from(w) * cos.abs() + f64::from(h) * sin.abs())
+        .round()
+        .max(1.0) as u32;
+    let new_h = (f64::from(w) * sin.abs() + f64::from(h) * cos.abs())
+        .round()
+        .max(1.0) as u32;
+    let (cx, cy) = (f64::from(w) / 2.0, f64::from(h) / 2.0);
+    let (ncx, ncy) = (f64::from(new_w) / 2.0, f64::from(new_h) / 2.0);
+
+    let out = RgbaImage::from_fn(new_w, new_h, |x, y| {
+        // 逆映射回原图坐标（y 轴向下时的顺时针旋转）。
+        let dx = f64::from(x) + 0.5 - ncx;
+        let dy = f64::from(y) + 0.5 - ncy;
+        let sx = dx * cos + dy * sin + cx - 0.5;
+        let sy = -dx * sin + dy * cos + cy - 0.5;
+        sample_bilinear(&src, sx, sy, background)
+    });
+    DynamicImage::ImageRgba8(out)
+}
+
+/// 直角（含 0°）对应的方向变换，其它角度返回 None。
+pub fn right_angle_orientation(degrees: f32) -> Option<Orientation> {
+    let normalized = degrees.rem_euclid(360.0);
+    let quarter = (normalized / 90.0).round();
+    if (normalized - quarter * 90.0).abs() > RIGHT_ANGLE_EPSILON {
+        return None;
+    }
+    Some(match quarter as u32 % 4 {
+        0 => Orientation::NoTransforms,
+        1 => Orientation::Rotate90,
+        2 => Orientation::Rotate180,
+        _ => Orientation::Rotate270,
+    })
+}
+
+/// 先应用 `first` 再应用 `then` 的合成方向变换。
+pub fn compose_orientation(first: Orientation, then: Orientation) -> Orientation {
+    let (k1, f1) = orientation_parts(first);
+    let (k2, f2) = orientation_parts(then);
+    // 翻转与旋转交换时旋转方向取反：F·R(k) = R(-k)·F。
+    if f2 {
+        orientation_from_parts((k2 + 4 - k1) % 4, !f1)
+    } else {
+        orientation_from_parts((k1 + k2) % 4, f1)
+    }
+}
+
+/// 把方向变换拆成「先水平翻转（可选）再顺时针旋转 k 个 90°」。
+fn orientation_parts(orientation: Orientation) -> (u8, bool) {
+    match orientation {
+        Orientation::NoTransforms => (0, false),
+        Orientation::Rotate90 => (1, false),
+        Orientation::Rotate180 => (2, false),
+        Orientation::Rotate270 => (3, false),
+        Orientation::FlipHorizontal => (0, true),
+        Orientation::Rotate270FlipH => (1, true),
+        Orientation::FlipVertical => (2, true),
+        Orientation::Rotate90FlipH => (3, true),
+    }
+}
+
+fn orientation_from_parts(quarters: u8, flipped: bool) -> Orientation {
+    match (quarters, flipped) {
+        (0, false) => Orientation::NoTransforms,
+        (1, false) => Orientation::Rotate90,
+        (2, false) => Orientation::Rotate180,
+        (3, false) => Orientation::Rotate270,
+        (0, true) => Orientation::FlipHorizontal,
+        (1, true) => Orientation::Rotate270FlipH,
+        (2, true) => Orientation::FlipVertical,
+        _ => Orientation::Rotate90FlipH,
+    }
+}
+
+/// 在预乘 alpha 空间插值，避免透明背景在边缘混出黑边；越界像素取 `background`。
+fn sample_bilinear(src: &RgbaImage, x: f64, y: f64, background: Rgba<u8>) -> Rgba<u8> {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let pixel = |px: f64, py: f64| -> Rgba<u8> {
+        if px < 0.0 || py < 0.0 || px >= f64::from(src.width()) || py >= f64::from(src.height()) {
+            background
+        } else {
+            *src.get_pixel(px as u32, py as u32)
+        }
+    };
+
+    let mut color = [0.0f64; 3];
+    let mut alpha = 0.0f64;
+    for (px, py, weight) in [
+        (x0, y0, (1.0 - fx) * (1.0 - fy)),
+        (x0 + 1.0, y0, fx * (1.0 - fy)),
+        (x0, y0 + 1.0, (1.0 - fx) * fy),
+        (x0 + 1.0, y0 + 1.0, fx * fy),
+    ] {
+        let Rgba([r, g, b, a]) = pixel(px, py);
+        let a = f64::from(a) / 255.0 * weight;
+        color[0] += f64::from(r) * a;
+        color[1] += f64::from(g) * a;
+        color[2] += f64::from(b) * a;
+        alpha += a;
+    }
+    if alpha <= f64::EPSILON {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel = |value: f64| (value / alpha).round().clamp(0.0, 255.0) as u8;
+    Rgba([
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        (alpha * 255.0).round().clamp(0.0, 255.0) as u8,
+    ])
+}
+
+/// 解析 `#rgb` / `#rrggbb` / `#rrggbbaa` 或 `transparent`。
+pub fn parse_color(value: &str) -> Option<Rgba<u8>> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("transparent") {
+        return Some(Rgba([0, 0, 0, 0]));
+    }
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        3 => {
+            let short = |i: usize| {
+                u8::from_str_radix(hex.get(i..i + 1)?, 16)
+                    .ok()
+                    .map(|v| v * 17)
+            };
+            Some(Rgba([short(0)?, short(1)?, short(2)?, 255]))
+        }
+        6 => Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 255])),
+        8 => Some(Rgba([channel(0)?, channel(2)?, channel(4)?, channel(6)?])),
+        _ => None,
+    }
+}
+
+/// Pad 阶段的背景。
+pub enum PadFill {
+    Color(Rgba<u8>),
+    /// 图片拉伸铺满画布后模糊作底。
+    Blur,
+}
+
+/// 解析 `w:h`（如 `4:5`）或小数形式的宽高比。
+pub fn parse_aspect(value: &str) -> Option<f64> {
+    let ratio = match value.split_once(':') {
+        Some((w, h)) => w.trim().parse::<f64>().ok()? / h.trim().parse::<f64>().ok()?,
+        None => value.trim().parse::<f64>().ok()?,
+    };
+    (ratio.is_finite() && ratio > 0.0).then_some(ratio)
+}
+
+/// 补边后的画布尺寸：给定宽高比时取容纳原图的最小画布，否则取目标宽高（未给的边沿用原图）。
+pub fn pad_canvas_size(
+    (width, height): (u32, u32),
+    target_width: Option<u32>,
+    target_height: Option<u32>,
+    aspect: Option<f64>,
+) -> (u32, u32) {
+    match aspect {
+        Some(ratio) if f64::from(width) / f64::from(height) < ratio => {
+            ((f64::from(height) * ratio).round() as u32, height)
+        }
+        Some(ratio) => (width, (f64::from(width) / ratio).round().max(1.0) as u32),
+        None => (
+            target_width.unwrap_or(width),
+            target_height.unwrap_or(height),
+        ),
+    }
+}
+
+/// 把图片按锚点放到 `canvas` 大小的画布上；图片大于画布时先等比缩小，保证不裁掉内容。
+pub fn pad(img: DynamicImage, canvas: (u32, u32), anchor: Anchor, fill: PadFill) -> DynamicImage {
+    let (canvas_w, canvas_h) = canvas;
+    let has_alpha = img.color().has_alpha();
+    let img = if img.width() > canvas_w || img.height() > canvas_h {
+        img.resize(canvas_w, canvas_h, image::imageops::FilterType::Lanczos3)
+    } else {
+        img
+    };
+
+    let (mut background, opaque) = match fill {
+        PadFill::Color(color) => (
+            RgbaImage::from_pixel(canvas_w, canvas_h, color),
+            color[3] == 255,
+        ),
+        PadFill::Blur => (blurred_backdrop(&img, canvas_w, canvas_h), true),
+    };
+    let (align_x, align_y) = anchor.alignment();
+    let x = (f64::from(canvas_w - img.width()) * align_x).round() as i64;
+    let y = (f64::from(canvas_h - img.height()) * align_y).round() as i64;
+    image::imageops::overlay(&mut background, &img.to_rgba8(), x, y);
+
+    let out = DynamicImage::ImageRgba8(background);
+    if has_alpha || !opaque {
+        out
+    } else {
+        DynamicImage::ImageRgb8(out.to_rgb8())
+    }
+}
+
+/// 先缩小再模糊再放大，得到与大半径模糊相近的效果而不必在全尺寸上模糊。
+fn blurred_backdrop(img: &DynamicImage, width: u32, height: u32) -> RgbaImage {
+    let triangle = image::imageops::FilterType::Triangle;
+    let small = img
+        .resize_exact((width / 16).max(1), (height / 16).max(1), triangle)
+        .blur(2.0);
+    let mut backdrop = small.resize_exact(width, height, triangle).to_rgba8();
+    for pixel in backdrop.pixels_mut() {
+        pixel[3] = 255;
+    }
+    backdrop
+}
+
+/// 九宫格锚点。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// 水平、垂直方向的对齐比例（0 靠左/上，1 靠右/下）。
+    pub fn alignment(self) -> (f64, f64) {
+        match self {
+            Self::TopLeft => (0.0, 0.0),
+            Self::Top => (0.5, 0.0),
+            Self::TopRight => (1.0, 0.0),
+            Self::Left => (0.0, 0.5),
+            Self::Center => (0.5, 0.5),
+            Self::Right => (1.0, 0.5),
+            Self::BottomLeft => (0.0, 1.0),
+            Self::Bottom => (0.5, 1.0),
+            Self::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Orientation; 8] = [
+        Orientation::NoTransforms,
+        Orientation::Rotate90,
+        Orientation::Rotate180,
+        Orientation::Rotate270,
+        Orientation::FlipHorizontal,
+        Orientation::FlipVertical,
+        Orientation::Rotate90FlipH,
+        Orientation::Rotate270FlipH,
+    ];
+
+    /// 每个像素都不同的 3×2 图，任何方向变换都能区分。
+    fn sample() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(3, 2, |x, y| {
+            Rgba([(x * 40) as u8, (y * 90) as u8, 0, 255])
+        }))
+    }
+
+    fn oriented(img: &DynamicImage, orientation: Orientation) -> DynamicImage {
+        let mut img = img.clone();
+        img.apply_orientation(orientation);
+        img
+    }
+
+    #[test]
+    fn composition_matches_applying_both_in_order() {
+        let img = sample();
+        for first in ALL {
+            for then in ALL {
+                let expected = oriented(&oriented(&img, first), then);
+                let composed = oriented(&img, compose_orientation(first, then));
+                assert_eq!(
+                    composed.to_rgba8(),
+                    expected.to_rgba8(),
+                    "{first:?} then {then:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn right_angles_map_to_orientations() {
+        assert_eq!(
+            right_angle_orientation(0.0),
+            Some(Orientation::NoTransforms)
+        );
+        assert_eq!(right_angle_orientation(90.0), Some(Orientation::Rotate90));
+        assert_eq!(right_angle_orientation(-90.0), Some(Orientation::Rotate270));
+        assert_eq!(right_angle_orientation(540.0), Some(Orientation::Rotate180));
+        assert_eq!(right_angle_orientation(89.995), Some(Orientation::Rotate90));
+        assert_eq!(right_angle_orientation(45.0), None);
+    }
+
+    #[test]
+    fn parses_aspect_ratios() {
+        assert_eq!(parse_aspect("4:5"), Some(0.8));
+        assert_eq!(parse_aspect(" 16 : 9 "), Some(16.0 / 9.0));
+        assert_eq!(parse_aspect("1.5"), Some(1.5));
+        for bad in [
+            "0:1", "1:0", "0", "-4:5", "4:", ":5", "4:5:6", "abc", "NaN", "",
+        ] {
+            assert_eq!(parse_aspect(bad), None, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn canvas_size_prefers_aspect_over_targets() {
+        // 给定宽高比时只扩展不足的一边，忽略目标宽高。
+        assert_eq!(
+            pad_canvas_size((100, 200), Some(50), Some(50), Some(1.0)),
+            (200, 200)
+        );
+        assert_eq!(
+            pad_canvas_size((300, 200), None, None, Some(1.0)),
+            (300, 300)
+        );
+        assert_eq!(
+            pad_canvas_size((400, 400), None, None, Some(0.8)),
+            (400, 500)
+        );
+        assert_eq!(
+            pad_canvas_size((400, 400), None, None, Some(2.0)),
+            (800, 400)
+        );
+
+        assert_eq!(
+            pad_canvas_size((100, 60), Some(120), Some(80), None),
+            (120, 80)
+        );
+        assert_eq!(pad_canvas_size((100, 60), None, Some(80), None), (100, 80));
+        assert_eq!(pad_canvas_size((100, 60), None, None, None), (100, 60));
+    }
+
+    #[test]
+    fn pad_shrinks_images_larger_than_the_canvas() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            100,
+            40,
+            image::Rgb([0, 0, 255]),
+        ));
+        let canvas = pad_canvas_size((100, 40), Some(50), Some(50), None);
+        assert_eq!(canvas, (50, 50));
+
+        let out = pad(img, canvas, Anchor::Center, PadFill::Color(red));
+        assert_eq!(out.color(), image::ColorType::Rgb8);
+        assert_eq!((out.width(), out.height()), (50, 50));
+        let out = out.to_rgba8();
+        // 等比缩到 50×20，垂直居中于 y = 15..35。
+        assert_eq!(*out.get_pixel(25, 14), red);
+        assert_eq!(*out.get_pixel(0, 15), blue);
+        assert_eq!(*out.get_pixel(49, 34), blue);
+        assert_eq!(*out.get_pixel(25, 35), red);
+    }
+
+    #[test]
+    fn pad_places_the_image_by_anchor() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([0, 255, 0, 255])));
+        let transparent = PadFill::Color(Rgba([0, 0, 0, 0]));
+        let out = pad(img, (6, 4), Anchor::BottomRight, transparent);
+        assert_eq!(out.color(), image::ColorType::Rgba8);
+        let out = out.to_rgba8();
+        assert_eq!(out.get_pixel(4, 2).0, [0, 255, 0, 255]);
+        assert_eq!(out.get_pixel(3, 2).0[3], 0);
+        assert_eq!(out.get_pixel(5, 1).0[3], 0);
+    }
+}
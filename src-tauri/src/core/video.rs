@@ -1,5 +1,14 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::error::{AppError, ErrorCode};
 use crate::{CompressMode, FfmpegCheckResult};
 
+/// 检查取消请求的间隔；ffmpeg 没有新进度输出时也按此频率轮询。
+const STOP_POLL_INTERVAL_MS: u64 = 200;
+
 pub fn check_ffmpeg() -> Result<FfmpegCheckResult, String> {
     let out = std::process::Command::new("ffmpeg")
         .args(["-version"])
@@ -24,14 +33,48 @@ pub fn check_ffmpeg() -> Result<FfmpegCheckResult, String> {
 }
 
 pub fn compress_video(path: &str, output_path: &str, mode: &CompressMode) -> Result<(), String> {
+    transcode_video(path, output_path, mode, |_| {}, || false)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// 转码结果中可从 ffmpeg 输出解析到的信息；解析不到时为 None。
+pub struct VideoInfo {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// 读取 ffmpeg 输出的线程发回的进度信息，时长均为微秒。
+enum FfmpegOutput {
+    Duration(u64),
+    OutTime(u64),
+}
+
+/// 转码单个视频：从 `-progress pipe:1` 读取已处理时长，对照 stderr 中的输入时长换算为 0-100 的进度。
+/// `should_stop` 返回 true 时杀掉 ffmpeg 进程、删除未写完的输出并返回取消错误。
+pub fn transcode_video<P, S>(
+    path: &str,
+    output_path: &str,
+    mode: &CompressMode,
+    mut on_progress: P,
+    mut should_stop: S,
+) -> Result<VideoInfo, AppError>
+where
+    P: FnMut(f32),
+    S: FnMut() -> bool,
+{
     let crf = match mode {
         CompressMode::Lossless => "0",
         CompressMode::VisuallyLossless => "17",
     };
 
-    let output = std::process::Command::new("ffmpeg")
+    let mut child = Command::new("ffmpeg")
         .args([
             "-y",
+            "-hide_banner",
+            "-nostats",
+            "-progress",
+            "pipe:1",
             "-i",
             path,
             "-c:v",
@@ -42,13 +85,192 @@ pub fn compress_video(path: &str, output_path: &str, mode: &CompressMode) -> Res
             "copy",
             output_path,
         ])
-        .output()
-        .map_err(|e| e.to_string())?;
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ffmpeg_error(format!("failed to start ffmpeg: {e}")).with_path(path))?;
+
+    // 两个管道都必须持续读取，否则缓冲区写满会卡住 ffmpeg。stderr 全文留到结束后解析尺寸与错误信息。
+    let (sender, receiver) = mpsc::channel::<FfmpegOutput>();
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if let Some(duration_us) = parse_duration_us(&line) {
+                    let _ = sender.send(FfmpegOutput::Duration(duration_us));
+                }
+                text.push_str(&line);
+                text.push('\n');
+            }
+            text
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Some(out_time_us) = parse_out_time_us(&line) {
+                    if sender.send(FfmpegOutput::OutTime(out_time_us)).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+    drop(sender);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg failed: {}", stderr));
+    let mut duration_us = None;
+    let mut stopped = false;
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(STOP_POLL_INTERVAL_MS)) {
+            // 只取第一个时长，即输入文件的时长。
+            Ok(FfmpegOutput::Duration(total)) => {
+                duration_us.get_or_insert(total);
+            }
+            Ok(FfmpegOutput::OutTime(out_time_us)) => {
+                if let Some(total) = duration_us.filter(|total| *total > 0) {
+                    on_progress((out_time_us as f64 / total as f64 * 100.0).clamp(0.0, 99.0) as f32);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if should_stop() {
+            stopped = true;
+            let _ = child.kill();
+            break;
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| ffmpeg_error(format!("failed to wait for ffmpeg: {e}")).with_path(path))?;
+    let stderr = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    if stopped {
+        let _ = std::fs::remove_file(output_path);
+        return Err(AppError::cancelled().with_path(path));
     }
+    if !status.success() {
+        let _ = std::fs::remove_file(output_path);
+        return Err(
+            ffmpeg_error(format!("FFmpeg failed: {}", last_lines(&stderr, 5))).with_path(path),
+        );
+    }
+
+    on_progress(100.0);
+    let (width, height) = parse_video_size(&stderr).unzip();
+    Ok(VideoInfo { width, height })
+}
+
+/// `-progress` 输出中的 `out_time_us=`；旧版 ffmpeg 的 `out_time_ms=` 实际同样是微秒。
+fn parse_out_time_us(line: &str) -> Option<u64> {
+    let value = line
+        .strip_prefix("out_time_us=")
+        .or_else(|| line.strip_prefix("out_time_ms="))?;
+    value.trim().parse().ok()
+}
 
-    Ok(())
+/// 解析 stderr 中输入信息的 `Duration: 00:01:02.03, start: ...`；直播流等为 `N/A`。
+fn parse_duration_us(line: &str) -> Option<u64> {
+    let start = line.find("Duration: ")? + "Duration: ".len();
+    let value = line[start..].split(',').next()?.trim();
+    let mut parts = value.split(':');
+    let hours = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts.next()?.parse::<f64>().ok()?;
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    Some(((hours * 3600.0 + minutes * 60.0 + seconds) * 1_000_000.0).round() as u64)
+}
+
+/// 取第一条视频流描述中的 `WxH`，如 `Stream #0:0: Video: h264 ..., 1920x1080 [SAR 1:1 ...]`。
+fn parse_video_size(stderr: &str) -> Option<(u32, u32)> {
+    let line = stderr.lines().find(|line| line.contains("Video:"))?;
+    line.split([',', ' '])
+        .filter_map(|token| {
+            let (w, h) = token.split_once('x')?;
+            Some((w.parse().ok()?, h.parse().ok()?))
+        })
+        .next()
+}
+
+fn last_lines(text: &str, count: usize) -> String {
+    let lines = text.lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+fn ffmpeg_error(message: String) -> AppError {
+    AppError::new(ErrorCode::FfmpegFailed, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ffmpeg 6 转码一个 1080p MP4 时 stderr 的开头部分。
+    const STDERR: &str = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'in.mp4':
+  Metadata:
+    major_brand     : isom
+    encoder         : Lavf60.16.100
+  Duration: 00:01:02.29, start: 0.000000, bitrate: 1205 kb/s
+  Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(progressive), 1920x1080 [SAR 1:1 DAR 16:9], 1070 kb/s, 30 fps, 30 tbr, 15360 tbn (default)
+  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, stereo, fltp, 128 kb/s (default)
+Stream mapping:
+  Stream #0:0 -> #0:0 (h264 (native) -> h264 (libx264))
+Output #0, mp4, to 'out.mp4':
+  Stream #0:0(und): Video: h264 (avc1 / 0x31637661), yuv420p(progressive), 1920x1080 [SAR 1:1 DAR 16:9], q=2-31, 30 fps, 15360 tbn (default)
+";
+
+    #[test]
+    fn parses_input_duration() {
+        let line = STDERR.lines().find(|l| l.contains("Duration")).unwrap();
+        assert_eq!(parse_duration_us(line), Some(62_290_000));
+        assert_eq!(
+            parse_duration_us("  Duration: 01:00:00.50, start: 0.000000, bitrate: N/A"),
+            Some(3_600_500_000)
+        );
+    }
+
+    #[test]
+    fn unknown_duration_is_none() {
+        assert_eq!(
+            parse_duration_us("  Duration: N/A, start: 0.000000, bitrate: N/A"),
+            None
+        );
+        assert_eq!(parse_duration_us("Stream mapping:"), None);
+    }
+
+    #[test]
+    fn parses_progress_out_time() {
+        assert_eq!(parse_out_time_us("out_time_us=4033333"), Some(4_033_333));
+        assert_eq!(parse_out_time_us("out_time_ms=4033333"), Some(4_033_333));
+        assert_eq!(parse_out_time_us("out_time=00:00:04.033333"), None);
+        assert_eq!(parse_out_time_us("progress=continue"), None);
+    }
+
+    #[test]
+    fn unknown_or_negative_out_time_is_none() {
+        assert_eq!(parse_out_time_us("out_time_us=N/A"), None);
+        assert_eq!(parse_out_time_us("out_time_ms=N/A"), None);
+        // 刚开始编码时 ffmpeg 会输出 INT64_MIN 附近的负值。
+        assert_eq!(parse_out_time_us("out_time_us=-9223372036854775807"), None);
+        assert_eq!(parse_out_time_us("out_time_ms=-577014"), None);
+    }
+
+    #[test]
+    fn parses_first_video_stream_size() {
+        assert_eq!(parse_video_size(STDERR), Some((1920, 1080)));
+        assert_eq!(
+            parse_video_size("  Stream #0:0: Video: vp9 (Profile 0), yuv420p(tv), 640x360, 25 fps"),
+            Some((640, 360))
+        );
+        assert_eq!(
+            parse_video_size("  Stream #0:0: Audio: mp3, 44100 Hz, stereo"),
+            None
+        );
+    }
 }
@@ -0,0 +1,313 @@
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+use crate::core::image::load_image;
+use crate::core::transform::Anchor;
+use crate::error::AppError;
+
+/// 未指定字体文件时使用的内置字体。
+const BUNDLED_FONT: &[u8] = include_bytes!("../../fonts/DejaVuSans.ttf");
+
+pub enum WatermarkSource {
+    /// PNG 等带透明通道的图片路径，等比缩放到长边为 `scale` × 短边，细长的图标也不会超出图片。
+    Image(String),
+    /// 文字，字号为 `scale` × 短边；`font` 为 TTF 路径，缺省用内置字体。
+    Text {
+        text: String,
+        font: Option<String>,
+        color: Rgba<u8>,
+    },
+}
+
+pub struct WatermarkSpec {
+    pub source: WatermarkSource,
+    pub anchor: Anchor,
+    /// 相对锚点位置的像素偏移，x 向右、y 向下为正。
+    pub offset: (i32, i32),
+    pub opacity: f32,
+    /// 相对图片短边的比例。
+    pub scale: f32,
+    /// 以锚点位置为起点铺满整幅图片，间距为水印尺寸的一半。
+    pub tiled: bool,
+}
+
+pub fn apply_watermark(img: DynamicImage, spec: &WatermarkSpec) -> Result<DynamicImage, AppError> {
+    let (width, height) = img.dimensions();
+    let target = (f64::from(width.min(height)) * f64::from(spec.scale)).max(1.0);
+    let mut mark = match &spec.source {
+        WatermarkSource::Image(path) => {
+            let side = target.round() as u32;
+            load_image(path)?
+                .resize(side, side, image::imageops::FilterType::Lanczos3)
+                .to_rgba8()
+        }
+        WatermarkSource::Text { text, font, color } => {
+            let font = load_font(font.as_deref())?;
+            render_text(&font, text, target as f32, *color)
+        }
+    };
+    let opacity = spec.opacity.clamp(0.0, 1.0);
+    for pixel in mark.pixels_mut() {
+        pixel[3] = (f32::from(pixel[3]) * opacity).round() as u8;
+    }
+
+    let (mark_w, mark_h) = (i64::from(mark.width()), i64::from(mark.height()));
+    let (align_x, align_y) = spec.anchor.alignment();
+    let x = ((i64::from(width) - mark_w) as f64 * align_x) as i64 + i64::from(spec.offset.0);
+    let y = ((i64::from(height) - mark_h) as f64 * align_y) as i64 + i64::from(spec.offset.1);
+
+    let has_alpha = img.color().has_alpha();
+    let mut canvas = img.to_rgba8();
+    if spec.tiled {
+        let step_x = mark_w + mark_w / 2;
+        let step_y = mark_h + mark_h / 2;
+        let mut tile_y = y.rem_euclid(step_y) - step_y;
+        while tile_y < i64::from(height) {
+            let mut tile_x = x.rem_euclid(step_x) - step_x;
+            while tile_x < i64::from(width) {
+                image::imageops::overlay(&mut canvas, &mark, tile_x, tile_y);
+                tile_x += step_x;
+            }
+            tile_y += step_y;
+        }
+    } else {
+        image::imageops::overlay(&mut canvas, &mark, x, y);
+    }
+    let img = DynamicImage::ImageRgba8(canvas);
+    Ok(if has_alpha {
+        img
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    })
+}
+
+fn load_font(path: Option<&str>) -> Result<FontArc, AppError> {
+    let font = match path {
+        Some(path) => {
+            let data = std::fs::read(path).map_err(|e| AppError::io(e, path))?;
+            FontArc::try_from_vec(data).map_err(|e| {
+                AppError::invalid_input(format!("invalid font: {e}")).with_path(path)
+            })?
+        }
+        None => FontArc::try_from_slice(BUNDLED_FONT)
+            .map_err(|e| AppError::internal(format!("bundled font: {e}")))?,
+    };
+    Ok(font)
+}
+
+/// 把（可多行的）文字渲染到刚好容纳它的透明画布上，逐行左对齐。
+fn render_text(font: &FontArc, text: &str, px: f32, color: Rgba<u8>) -> RgbaImage {
+    let scaled = font.as_scaled(PxScale::from(px));
+    let line_height = scaled.height() + scaled.line_gap();
+    let lines: Vec<&str> = text.lines().collect();
+
+    let mut glyphs = Vec::new();
+    let mut max_width = 0.0f32;
+    for (row, line) in lines.iter().enumerate() {
+        let baseline = scaled.ascent() + line_height * row as f32;
+        let mut caret = 0.0f32;
+        let mut previous = None;
+        for ch in line.chars() {
+            let id = scaled.glyph_id(ch);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, id);
+            }
+            glyphs.push(id.with_scale_and_position(px, point(caret, baseline)));
+            caret += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        max_width = max_width.max(caret);
+    }
+
+    let width = max_width.ceil().max(1.0) as u32;
+    let height = (line_height * lines.len().max(1) as f32 - scaled.line_gap())
+        .ceil()
+        .max(1.0) as u32;
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([color[0], color[1], color[2], 0]));
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let x = bounds.min.x as i64 + i64::from(gx);
+            let y = bounds.min.y as i64 + i64::from(gy);
+            if x < 0 || y < 0 || x >= i64::from(width) || y >= i64::from(height) {
+                return;
+            }
+            let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+            let alpha = (coverage.clamp(0.0, 1.0) * f32::from(color[3])).round() as u8;
+            pixel[3] = pixel[3].max(alpha);
+        });
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    /// 写一个纯红的临时 PNG 图标，返回路径。
+    fn logo(name: &str, width: u32, height: u32) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "cruncher-watermark-{}-{name}.png",
+            std::process::id()
+        ));
+        RgbaImage::from_pixel(width, height, RED)
+            .save(&path)
+            .unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn black(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([0, 0, 0])))
+    }
+
+    fn spec(source: WatermarkSource, anchor: Anchor, offset: (i32, i32)) -> WatermarkSpec {
+        WatermarkSpec {
+            source,
+            anchor,
+            offset,
+            opacity: 1.0,
+            scale: 0.1,
+            tiled: false,
+        }
+    }
+
+    /// 红色分量超过一半的像素的外接矩形 (x0, y0, x1, y1)，右下为开区间。
+    fn red_bounds(img: &DynamicImage) -> Option<(u32, u32, u32, u32)> {
+        let rgb = img.to_rgb8();
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for (x, y, pixel) in rgb.enumerate_pixels() {
+            if pixel[0] > 127 {
+                let (x0, y0, x1, y1) = bounds.unwrap_or((x, y, x + 1, y + 1));
+                bounds = Some((x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)));
+            }
+        }
+        bounds
+    }
+
+    #[test]
+    fn places_image_at_anchor_with_offset() {
+        let path = logo("anchor", 10, 10);
+        let cases = [
+            (Anchor::TopLeft, (0, 0), (0, 0)),
+            (Anchor::TopLeft, (7, 3), (7, 3)),
+            (Anchor::Center, (0, 0), (45, 45)),
+            (Anchor::BottomRight, (0, 0), (90, 90)),
+            (Anchor::BottomRight, (-5, -8), (85, 82)),
+            (Anchor::Bottom, (0, -1), (45, 89)),
+        ];
+        for (anchor, offset, (x, y)) in cases {
+            let out = apply_watermark(
+                black(100, 100),
+                &spec(WatermarkSource::Image(path.clone()), anchor, offset),
+            )
+            .unwrap();
+            assert_eq!(red_bounds(&out), Some((x, y, x + 10, y + 10)), "{anchor:?}");
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn scales_opacity() {
+        let path = logo("opacity", 10, 10);
+        let with_opacity = |opacity| {
+            let spec = WatermarkSpec {
+                opacity,
+                ..spec(
+                    WatermarkSource::Image(path.clone()),
+                    Anchor::TopLeft,
+                    (0, 0),
+                )
+            };
+            apply_watermark(black(100, 100), &spec).unwrap()
+        };
+        let half = with_opacity(0.5).to_rgb8();
+        let invisible = with_opacity(0.0);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(half.get_pixel(5, 5)[0].abs_diff(128) <= 1);
+        assert_eq!(half.get_pixel(50, 50), &Rgb([0, 0, 0]));
+        assert_eq!(red_bounds(&invisible), None);
+    }
+
+    #[test]
+    fn tiles_cover_the_whole_canvas() {
+        let path = logo("tiled", 10, 10);
+        let out = apply_watermark(
+            black(100, 80),
+            &WatermarkSpec {
+                tiled: true,
+                scale: 0.125,
+                ..spec(WatermarkSource::Image(path.clone()), Anchor::Center, (3, 0))
+            },
+        )
+        .unwrap()
+        .to_rgb8();
+        let _ = std::fs::remove_file(path);
+        // 水印 10×10，步长为其 1.5 倍：任意 15×15 的区域内都有水印。
+        for y0 in 0..80 - 15 {
+            for x0 in 0..100 - 15 {
+                let covered =
+                    (y0..y0 + 15).any(|y| (x0..x0 + 15).any(|x| out.get_pixel(x, y)[0] > 127));
+                assert!(covered, "uncovered block at ({x0}, {y0})");
+            }
+        }
+        // 锚点位置本身有一块水印。
+        assert_eq!(out.get_pixel(48, 35)[0], 255);
+    }
+
+    #[test]
+    fn tall_logo_fits_the_shorter_edge() {
+        let path = logo("tall", 10, 40);
+        let out = apply_watermark(
+            black(200, 100),
+            &WatermarkSpec {
+                scale: 0.2,
+                ..spec(
+                    WatermarkSource::Image(path.clone()),
+                    Anchor::TopLeft,
+                    (0, 0),
+                )
+            },
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(path);
+        // 长边 20 = 0.2 × 短边 100，宽度按比例为 5。
+        assert_eq!(red_bounds(&out), Some((0, 0, 5, 20)));
+    }
+
+    #[test]
+    fn keeps_the_input_colour_type() {
+        let text = || WatermarkSource::Text {
+            text: "cruncher".to_string(),
+            font: None,
+            color: RED,
+        };
+        let rgb = apply_watermark(black(64, 64), &spec(text(), Anchor::Center, (0, 0))).unwrap();
+        assert_eq!(rgb.color(), image::ColorType::Rgb8);
+
+        let rgba = DynamicImage::ImageRgba8(RgbaImage::new(64, 64));
+        let rgba = apply_watermark(rgba, &spec(text(), Anchor::Center, (0, 0))).unwrap();
+        assert_eq!(rgba.color(), image::ColorType::Rgba8);
+    }
+
+    #[test]
+    fn renders_text_lines_onto_a_fitted_canvas() {
+        let font = load_font(None).unwrap();
+        let single = render_text(&font, "Wm", 20.0, RED);
+        let double = render_text(&font, "Wm\nWm", 20.0, RED);
+        assert!(single.height() >= 20 && single.height() < 30);
+        assert!(double.height() > single.height() * 3 / 2);
+        assert_eq!(double.width(), single.width());
+        assert!(single.pixels().any(|pixel| pixel[3] == 255));
+        assert!(single.pixels().all(|pixel| pixel.0[..3] == RED.0[..3]));
+        // 空文字也得到 1×1 的透明画布，而不是 0 尺寸。
+        let empty = render_text(&font, "", 20.0, RED);
+        assert!(empty.width() >= 1 && empty.height() >= 1);
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::pipeline::stage::PipelineStageKind;
+
+/// 前端据此给出针对性提示的稳定错误码，新增可以，已有取值不要改名。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    Cancelled,
+    FileNotFound,
+    PermissionDenied,
+    DiskFull,
+    Io,
+    UnsupportedImage,
+    DecodeFailed,
+    EncodeFailed,
+    InvalidInput,
+    CropOutOfBounds,
+    InvalidPipeline,
+    JobNotFound,
+    InvalidJobState,
+    PresetNotFound,
+    WatchNotFound,
+    FfmpegFailed,
+    Internal,
+}
+
+/// 图片处理、流水线与任务子系统统一使用的错误类型，序列化后直接返回给前端。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub path: Option<String>,
+    pub stage: Option<PipelineStageKind>,
+    /// 底层 IO 错误类型（`std::io::ErrorKind` 的名称），便于区分磁盘满、无权限等情况。
+    pub io_kind: Option<String>,
+    pub job_id: Option<String>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            path: None,
+            stage: None,
+            io_kind: None,
+            job_id: None,
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        Self::new(ErrorCode::Cancelled, "Job cancelled")
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn invalid_pipeline(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidPipeline, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    pub fn job_not_found(job_id: &str) -> Self {
+        Self::new(ErrorCode::JobNotFound, format!("Job not found: {job_id}")).with_job(job_id)
+    }
+
+    pub fn invalid_job_state(job_id: &str, message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidJobState, message).with_job(job_id)
+    }
+
+    pub fn io(err: std::io::Error, path: impl AsRef<Path>) -> Self {
+        let code = match err.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::FileNotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            std::io::ErrorKind::StorageFull => ErrorCode::DiskFull,
+            _ => ErrorCode::Io,
+        };
+        let mut error = Self::new(code, err.to_string()).with_path(path);
+        error.io_kind = Some(format!("{:?}", err.kind()));
+        error
+    }
+
+    pub fn image(err: image::ImageError, path: impl AsRef<Path>) -> Self {
+        match err {
+            image::ImageError::IoError(io) => Self::io(io, path),
+            image::ImageError::Unsupported(e) => {
+                Self::new(ErrorCode::UnsupportedImage, e.to_string()).with_path(path)
+            }
+            image::ImageError::Decoding(e) => {
+                Self::new(ErrorCode::DecodeFailed, e.to_string()).with_path(path)
+            }
+            image::ImageError::Encoding(e) => {
+                Self::new(ErrorCode::EncodeFailed, e.to_string()).with_path(path)
+            }
+            other => Self::invalid_input(other.to_string()).with_path(path),
+        }
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().to_string());
+        self
+    }
+
+    /// 仅在尚未标注阶段时补充，保留最内层的阶段信息。
+    pub fn with_stage(mut self, stage: PipelineStageKind) -> Self {
+        self.stage.get_or_insert(stage);
+        self
+    }
+
+    pub fn with_job(mut self, job_id: &str) -> Self {
+        self.job_id = Some(job_id.to_string());
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.code == ErrorCode::Cancelled
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
@@ -0,0 +1,258 @@
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::error::AppError;
+use crate::job::manager::default_concurrency;
+use crate::job::types::ImageJobRequest;
+use crate::pipeline::executor::estimate_pipeline_for_file;
+use crate::pipeline::stage::PipelineStage;
+
+/// 单个文件的试运行结果；估算失败时只有 `error`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEstimate {
+    pub file_index: usize,
+    pub input_path: String,
+    pub input_size_bytes: Option<u64>,
+    pub predicted_size_bytes: Option<u64>,
+    /// 输入体积减预测体积，输出更大时为负数。
+    pub predicted_saved_bytes: Option<i64>,
+    pub compression_ratio: Option<f32>,
+    pub output_width: Option<u32>,
+    pub output_height: Option<u32>,
+    pub output_format: Option<String>,
+    pub error: Option<AppError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageJobEstimate {
+    pub total_files: usize,
+    pub sampled_files: usize,
+    /// 抽样估算时为 true，此时汇总值按样本外推到全部文件。
+    pub extrapolated: bool,
+    /// 全部输入文件的体积之和（不限于样本）。
+    pub total_input_bytes: u64,
+    /// 按成功估算文件的整体压缩比折算到全部输入；没有文件估算成功时为 None。
+    pub predicted_output_bytes: Option<u64>,
+    pub predicted_saved_bytes: Option<i64>,
+    pub compression_ratio: Option<f32>,
+    pub files: Vec<FileEstimate>,
+}
+
+/// 对请求（或其中 `sample_size` 个随机文件）做试运行，按 CPU 数并行，不写任何文件。
+/// 调用方负责事先校验请求并解析流水线。
+pub fn estimate_job(request: &ImageJobRequest, sample_size: Option<usize>) -> ImageJobEstimate {
+    let total_files = request.inputs.len();
+    let indices = match sample_size {
+        Some(n) => sample_indices(total_files, n, random_seed()),
+        None => (0..total_files).collect(),
+    };
+    let stages = request.pipeline.clone().unwrap_or_default();
+    let auto_orient = request
+        .options
+        .as_ref()
+        .and_then(|opts| opts.auto_orient)
+        .unwrap_or(true);
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(indices.len()));
+    let workers = default_concurrency().clamp(1, indices.len().max(1));
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(&file_index) = indices.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let estimate = estimate_file(request, &stages, auto_orient, file_index);
+                    if let Ok(mut results) = results.lock() {
+                        results.push(estimate);
+                    }
+                }
+            });
+        }
+    });
+    let files = results.into_inner().unwrap_or_default();
+
+    let total_input_bytes = request
+        .inputs
+        .iter()
+        .filter_map(|input| std::fs::metadata(input).ok())
+        .map(|meta| meta.len())
+        .sum::<u64>();
+    summarize(total_files, total_input_bytes, files)
+}
+
+/// 按成功估算文件的整体压缩比，把预测体积外推到全部 `total_input_bytes`。
+fn summarize(
+    total_files: usize,
+    total_input_bytes: u64,
+    mut files: Vec<FileEstimate>,
+) -> ImageJobEstimate {
+    files.sort_by_key(|file| file.file_index);
+    let (sample_input, sample_output) = files
+        .iter()
+        .filter_map(|file| Some((file.input_size_bytes?, file.predicted_size_bytes?)))
+        .fold((0u64, 0u64), |(input, output), (i, o)| {
+            (input + i, output + o)
+        });
+    let compression_ratio = (sample_input > 0).then(|| sample_output as f64 / sample_input as f64);
+    let predicted_output_bytes =
+        compression_ratio.map(|ratio| (total_input_bytes as f64 * ratio).round() as u64);
+
+    ImageJobEstimate {
+        total_files,
+        sampled_files: files.len(),
+        extrapolated: files.len() < total_files,
+        total_input_bytes,
+        predicted_output_bytes,
+        predicted_saved_bytes: predicted_output_bytes
+            .map(|output| total_input_bytes as i64 - output as i64),
+        compression_ratio: compression_ratio.map(|ratio| ratio as f32),
+        files,
+    }
+}
+
+fn estimate_file(
+    request: &ImageJobRequest,
+    stages: &[PipelineStage],
+    auto_orient: bool,
+    file_index: usize,
+) -> FileEstimate {
+    let input_path = request.inputs[file_index].clone();
+    let input_size_bytes = std::fs::metadata(&input_path).map(|m| m.len()).ok();
+    let mut estimate = FileEstimate {
+        file_index,
+        input_path,
+        input_size_bytes,
+        predicted_size_bytes: None,
+        predicted_saved_bytes: None,
+        compression_ratio: None,
+        output_width: None,
+        output_height: None,
+        output_format: None,
+        error: None,
+    };
+    match estimate_pipeline_for_file(&estimate.input_path, stages, auto_orient) {
+        Ok(output) => {
+            estimate.predicted_size_bytes = Some(output.size_bytes);
+            estimate.predicted_saved_bytes =
+                input_size_bytes.map(|input| input as i64 - output.size_bytes as i64);
+            estimate.compression_ratio = input_size_bytes
+                .filter(|input| *input > 0)
+                .map(|input| output.size_bytes as f32 / input as f32);
+            estimate.output_width = Some(output.width);
+            estimate.output_height = Some(output.height);
+            estimate.output_format = Some(output.format);
+        }
+        Err(err) => estimate.error = Some(err),
+    }
+    estimate
+}
+
+/// 不引入随机数依赖：取标准库每次随机生成的哈希种子。
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// 从 `total` 个文件中不重复地抽取 `n` 个，按原顺序返回；`n` 不小于 `total` 时返回全部。
+/// 由 `seed` 驱动 xorshift 做部分 Fisher–Yates 洗牌，相同种子得到相同样本。
+fn sample_indices(total: usize, n: usize, seed: u64) -> Vec<usize> {
+    let n = n.min(total);
+    let mut seed = seed | 1;
+    let mut indices = (0..total).collect::<Vec<_>>();
+    for i in 0..n {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let j = i + (seed % (total - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(n);
+    indices.sort_unstable();
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate(file_index: usize, input: u64, predicted: Option<u64>) -> FileEstimate {
+        FileEstimate {
+            file_index,
+            input_path: format!("/in/{file_index}.png"),
+            input_size_bytes: Some(input),
+            predicted_size_bytes: predicted,
+            predicted_saved_bytes: None,
+            compression_ratio: None,
+            output_width: None,
+            output_height: None,
+            output_format: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn sample_indices_are_unique_sorted_and_in_range() {
+        for seed in [0, 1, 42, u64::MAX] {
+            for (total, n) in [(10, 3), (100, 17), (5, 4), (1, 1)] {
+                let indices = sample_indices(total, n, seed);
+                assert_eq!(indices.len(), n);
+                assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+                assert!(indices.iter().all(|&index| index < total));
+            }
+        }
+    }
+
+    #[test]
+    fn sample_indices_depend_only_on_the_seed() {
+        assert_eq!(sample_indices(1000, 10, 7), sample_indices(1000, 10, 7));
+        assert_ne!(sample_indices(1000, 10, 7), sample_indices(1000, 10, 8));
+    }
+
+    #[test]
+    fn oversized_sample_returns_every_file() {
+        assert_eq!(sample_indices(4, 4, 9), [0, 1, 2, 3]);
+        assert_eq!(sample_indices(4, 10, 9), [0, 1, 2, 3]);
+        assert!(sample_indices(0, 3, 9).is_empty());
+    }
+
+    #[test]
+    fn totals_are_extrapolated_from_the_sample_ratio() {
+        // 样本 1000 + 3000 字节压到 400 + 600，整体比例 0.25；失败的文件不参与。
+        let files = vec![
+            estimate(7, 3000, Some(600)),
+            estimate(2, 1000, Some(400)),
+            estimate(5, 5000, None),
+        ];
+        let summary = summarize(10, 20_000, files);
+        assert!(summary.extrapolated);
+        assert_eq!(summary.sampled_files, 3);
+        assert_eq!(summary.compression_ratio, Some(0.25));
+        assert_eq!(summary.predicted_output_bytes, Some(5_000));
+        assert_eq!(summary.predicted_saved_bytes, Some(15_000));
+        let order = summary
+            .files
+            .iter()
+            .map(|file| file.file_index)
+            .collect::<Vec<_>>();
+        assert_eq!(order, [2, 5, 7]);
+    }
+
+    #[test]
+    fn growing_output_gives_negative_savings() {
+        let summary = summarize(1, 1000, vec![estimate(0, 1000, Some(1500))]);
+        assert!(!summary.extrapolated);
+        assert_eq!(summary.predicted_output_bytes, Some(1500));
+        assert_eq!(summary.predicted_saved_bytes, Some(-500));
+    }
+
+    #[test]
+    fn totals_are_unset_when_nothing_was_estimated() {
+        let summary = summarize(2, 1000, vec![estimate(0, 1000, None)]);
+        assert_eq!(summary.compression_ratio, None);
+        assert_eq!(summary.predicted_output_bytes, None);
+        assert_eq!(summary.predicted_saved_bytes, None);
+    }
+}
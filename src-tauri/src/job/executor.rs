@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::AppError;
+use crate::job::image_executor::ImageExecutor;
+use crate::job::types::{JobFileStats, JobRequest};
+use crate::job::video_executor::VideoExecutor;
+use crate::pipeline::stage::PipelineStageKind;
+
+/// 单个文件处理中的一次进度上报。
+#[derive(Debug, Clone, Copy)]
+pub struct FileProgress {
+    /// 仅有分阶段流水线的执行器（如图片）才会设置。
+    pub stage: Option<PipelineStageKind>,
+    pub stage_progress: f32,
+    /// 该文件的整体进度（0-100），用于汇总任务总进度。
+    pub file_progress: f32,
+}
+
+/// 某一媒体类型的单文件处理器。任务的调度、暂停/取消、持久化与事件派发由任务执行循环统一负责，
+/// 执行器只需给出输出路径并同步处理单个文件。
+pub trait MediaExecutor: Send + Sync {
+    /// 请求未指定并发数时的默认值。
+    fn default_concurrency(&self) -> usize;
+
+    fn output_path(&self, input_path: &str) -> String;
+
+    /// 在阻塞线程中调用。`should_stop` 返回 true 时应尽快返回（通常为取消错误），
+    /// 已写出的部分输出由执行器自行清理。
+    fn process_file(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        on_progress: &mut dyn FnMut(FileProgress),
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<JobFileStats, AppError>;
+}
+
+pub fn executor_for(request: &JobRequest) -> Arc<dyn MediaExecutor> {
+    match request {
+        JobRequest::Image(request) => Arc::new(ImageExecutor::new(request)),
+        JobRequest::Video(request) => Arc::new(VideoExecutor::new(request)),
+    }
+}
+
+/// `{stem}_compressed.{ext}`，未指定输出目录时与输入文件同目录。
+pub fn build_output_path(input_path: &str, output_dir: Option<&str>, ext: &str) -> String {
+    let input = Path::new(input_path);
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let file_name = format!("{stem}_compressed.{ext}");
+
+    if let Some(dir) = output_dir {
+        return Path::new(dir).join(file_name).to_string_lossy().to_string();
+    }
+
+    let parent = input
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    parent.join(file_name).to_string_lossy().to_string()
+}
+
+pub fn build_file_stats(
+    started: Instant,
+    input_size_bytes: Option<u64>,
+    output_path: &str,
+    output_width: u32,
+    output_height: u32,
+    output_format: String,
+) -> JobFileStats {
+    let output_size_bytes = std::fs::metadata(output_path).map(|m| m.len()).ok();
+    JobFileStats {
+        input_size_bytes,
+        output_size_bytes,
+        compression_ratio: match (input_size_bytes, output_size_bytes) {
+            (Some(input), Some(output)) if input > 0 => Some(output as f32 / input as f32),
+            _ => None,
+        },
+        output_width,
+        output_height,
+        output_format,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::Instant;
+
+use crate::error::AppError;
+use crate::job::executor::{build_file_stats, build_output_path, FileProgress, MediaExecutor};
+use crate::job::manager::default_concurrency;
+use crate::job::types::{ImageJobRequest, JobFileStats};
+use crate::pipeline::executor::execute_pipeline_for_file;
+use crate::pipeline::stage::{stage_weight, PipelineStage};
+use crate::pipeline::validator::{resolve_output_format, resolve_pipeline};
+
+/// 按请求中的流水线逐文件处理图片。
+pub struct ImageExecutor {
+    output_dir: Option<String>,
+    stages: Vec<PipelineStage>,
+    auto_orient: bool,
+}
+
+impl ImageExecutor {
+    pub fn new(request: &ImageJobRequest) -> Self {
+        Self {
+            output_dir: request.output_dir.clone(),
+            stages: request
+                .pipeline
+                .clone()
+                .unwrap_or_else(|| resolve_pipeline(request)),
+            auto_orient: request
+                .options
+                .as_ref()
+                .and_then(|opts| opts.auto_orient)
+                .unwrap_or(true),
+        }
+    }
+}
+
+impl MediaExecutor for ImageExecutor {
+    /// 图片处理是 CPU 密集型，按 CPU 数并行。
+    fn default_concurrency(&self) -> usize {
+        default_concurrency()
+    }
+
+    fn output_path(&self, input_path: &str) -> String {
+        build_output_path(
+            input_path,
+            self.output_dir.as_deref(),
+            &resolve_output_extension(input_path, &self.stages),
+        )
+    }
+
+    /// 将各阶段进度按阶段权重折算为文件进度。
+    fn process_file(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        on_progress: &mut dyn FnMut(FileProgress),
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<JobFileStats, AppError> {
+        let total_stage_weight = self
+            .stages
+            .iter()
+            .map(|s| stage_weight(s.kind()))
+            .sum::<f32>()
+            .max(1.0);
+        let started = Instant::now();
+        let input_size_bytes = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let mut completed_weight = 0.0f32;
+        let output = execute_pipeline_for_file(
+            input_path,
+            output_path,
+            &self.stages,
+            self.auto_orient,
+            |stage, stage_progress| {
+                let stage_p = stage_progress.clamp(0.0, 100.0);
+                let file_progress = ((completed_weight + stage_weight(stage) * (stage_p / 100.0))
+                    / total_stage_weight)
+                    * 100.0;
+                on_progress(FileProgress {
+                    stage: Some(stage),
+                    stage_progress: stage_p,
+                    file_progress,
+                });
+
+                if stage_p >= 100.0 {
+                    completed_weight += stage_weight(stage);
+                }
+            },
+            should_stop,
+        )?;
+
+        Ok(build_file_stats(
+            started,
+            input_size_bytes,
+            output_path,
+            output.width,
+            output.height,
+            output.format,
+        ))
+    }
+}
+
+fn resolve_output_extension(input_path: &str, stages: &[PipelineStage]) -> String {
+    let normalized = resolve_output_format(input_path, stages);
+    if normalized.is_empty() {
+        return "png".to_string();
+    }
+    match normalized.as_str() {
+        "jpeg" => "jpg".to_string(),
+        _ => normalized,
+    }
+}
@@ -4,23 +4,67 @@ use std::sync::{
     Arc, Mutex,
 };
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
 
-use crate::job::types::{ImageJobRequest, ImageJobState, JobFileState, JobStatus};
+use crate::error::AppError;
+use crate::job::scheduler::JobQueue;
+use crate::job::store::{JobStore, JobStoreWriter, PersistedJob};
+use crate::job::types::{
+    JobFileState, JobFileStats, JobKind, JobRequest, JobState, JobStatus, RetentionPolicy,
+};
+use crate::progress::throttle::ProgressSettings;
+
+/// 文件级状态变化的最小落盘间隔，避免大批量任务每完成一个文件就整份重写。
+const PERSIST_INTERVAL_MS: u64 = 1000;
 
 #[derive(Clone)]
 pub struct JobManager {
     inner: Arc<Mutex<Inner>>,
+    store: Option<Arc<JobStore>>,
+    /// 任务记录的写盘与删除都经由它串行执行，不在持锁期间做磁盘 IO。
+    writer: Option<Arc<JobStoreWriter>>,
+    /// 槽位释放、队列调整、任务暂停/取消时唤醒等待槽位的任务。
+    slot_notify: Arc<Notify>,
 }
 
 struct Inner {
     next_id: u64,
     jobs: HashMap<String, ManagedJob>,
+    queue: JobQueue,
+    retention: RetentionPolicy,
+    progress_settings: ProgressSettings,
+}
+
+/// 全局文件槽位，释放时归还给调度队列。
+pub struct FileSlot {
+    manager: JobManager,
+    /// 任务因拿到这个槽位从 Pending 进入 Running。
+    pub started_job: bool,
+}
+
+impl Drop for FileSlot {
+    fn drop(&mut self) {
+        if let Ok(mut guard) = self.manager.inner.lock() {
+            guard.queue.release();
+        }
+        self.manager.slot_notify.notify_waiters();
+    }
 }
 
 struct ManagedJob {
-    request: ImageJobRequest,
-    state: ImageJobState,
+    request: JobRequest,
+    state: JobState,
     cancel_flag: Arc<AtomicBool>,
+    pause_flag: Arc<AtomicBool>,
+    /// 逐文件的停止标记，处理中的文件在下一个阶段边界检查。
+    file_stop_flags: Arc<Vec<AtomicBool>>,
+    /// 处理中文件被要求停止后应落定的状态（Cancelled 或 Skipped）。
+    stop_requests: HashMap<usize, JobStatus>,
+    /// 是否已有执行协程在处理该任务（不落盘，重启后恒为 false）。
+    active: bool,
+    /// 执行中被删除：等执行协程收尾并发出结束事件后再移除。
+    delete_requested: bool,
+    last_persisted_ms: u64,
 }
 
 impl Default for JobManager {
@@ -29,20 +73,78 @@ impl Default for JobManager {
             inner: Arc::new(Mutex::new(Inner {
                 next_id: 1,
                 jobs: HashMap::new(),
+                queue: JobQueue::new(default_concurrency()),
+                retention: RetentionPolicy::default(),
+                progress_settings: ProgressSettings::default(),
             })),
+            store: None,
+            writer: None,
+            slot_notify: Arc::new(Notify::new()),
         }
     }
 }
 
 impl JobManager {
-    pub fn create_job(&self, request: ImageJobRequest) -> Result<String, String> {
-        let mut guard = self.inner.lock().map_err(|_| "Job manager poisoned")?;
-        let job_id = format!("img-job-{}", guard.next_id);
+    /// 从磁盘恢复历史任务；上次退出时未完成的任务回到 Pending（已暂停的保持 Paused），等待 `resume_image_job`。
+    pub fn with_store(store: JobStore) -> Self {
+        let mut jobs = HashMap::new();
+        let mut next_id = 1u64;
+        for PersistedJob { request, mut state } in store.load_all().unwrap_or_default() {
+            if let Some(n) = parse_job_number(&state.job_id) {
+                next_id = next_id.max(n + 1);
+            }
+            if matches!(
+                state.status,
+                JobStatus::Pending | JobStatus::Running | JobStatus::Paused
+            ) {
+                reset_interrupted_files(&mut state);
+                if !matches!(state.status, JobStatus::Paused) {
+                    state.status = JobStatus::Pending;
+                }
+                state.completed_at_ms = None;
+            }
+            jobs.insert(
+                state.job_id.clone(),
+                ManagedJob {
+                    request,
+                    file_stop_flags: new_file_stop_flags(state.files.len()),
+                    state,
+                    cancel_flag: Arc::new(AtomicBool::new(false)),
+                    pause_flag: Arc::new(AtomicBool::new(false)),
+                    stop_requests: HashMap::new(),
+                    active: false,
+                    delete_requested: false,
+                    last_persisted_ms: 0,
+                },
+            );
+        }
+
+        let retention = store.load_retention();
+        let store = Arc::new(store);
+        let manager = Self {
+            inner: Arc::new(Mutex::new(Inner {
+                next_id,
+                jobs,
+                queue: JobQueue::new(default_concurrency()),
+                retention,
+                progress_settings: ProgressSettings::default(),
+            })),
+            writer: Some(Arc::new(JobStoreWriter::spawn(store.clone()))),
+            store: Some(store),
+            slot_notify: Arc::new(Notify::new()),
+        };
+        manager.apply_retention();
+        manager
+    }
+
+    pub fn create_job(&self, request: JobRequest) -> Result<String, AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let job_id = format!("{}{}", job_id_prefix(request.kind()), guard.next_id);
         guard.next_id += 1;
         let now = now_ms();
 
         let files = request
-            .inputs
+            .inputs()
             .iter()
             .map(|input| JobFileState {
                 input_path: input.clone(),
@@ -50,70 +152,276 @@ impl JobManager {
                 status: JobStatus::Pending,
                 progress: 0.0,
                 error: None,
+                stats: None,
             })
             .collect::<Vec<_>>();
 
-        let state = ImageJobState {
+        let state = JobState {
             job_id: job_id.clone(),
+            kind: request.kind(),
+            parent_job_id: request.parent_job_id().map(str::to_string),
+            queue_position: None,
             status: JobStatus::Pending,
             created_at_ms: now,
             started_at_ms: None,
             completed_at_ms: None,
-            total_files: request.inputs.len(),
+            total_files: files.len(),
             completed_files: 0,
             failed_files: 0,
             cancelled_files: 0,
+            skipped_files: 0,
             overall_progress: 0.0,
+            total_input_bytes: 0,
+            total_output_bytes: 0,
+            total_saved_bytes: 0,
+            average_compression_ratio: None,
             files,
         };
 
-        guard.jobs.insert(
-            job_id.clone(),
-            ManagedJob {
-                request,
-                state,
-                cancel_flag: Arc::new(AtomicBool::new(false)),
-            },
-        );
+        let mut job = ManagedJob {
+            request,
+            file_stop_flags: new_file_stop_flags(state.files.len()),
+            state,
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+            pause_flag: Arc::new(AtomicBool::new(false)),
+            stop_requests: HashMap::new(),
+            active: true,
+            delete_requested: false,
+            last_persisted_ms: 0,
+        };
+        self.persist(&mut job, true);
+        guard.queue.enqueue(&job_id, job.request.priority());
+        guard.jobs.insert(job_id.clone(), job);
+        drop(guard);
+        self.apply_retention();
         Ok(job_id)
     }
 
-    pub fn get_request(&self, job_id: &str) -> Result<ImageJobRequest, String> {
-        let guard = self.inner.lock().map_err(|_| "Job manager poisoned")?;
+    /// 恢复已暂停或中断的任务：仅保留 Pending/Running 文件待处理，已完成的文件不再重做。
+    /// 返回 true 表示需要重新启动执行协程；若原协程仍在收尾暂停，则直接让其继续。
+    pub fn resume_job(&self, job_id: &str) -> Result<bool, AppError> {
+        let resumed = self.with_job_and_queue(job_id, |job, queue| {
+            if job.active {
+                if !matches!(job.state.status, JobStatus::Paused) {
+                    return Err(AppError::invalid_job_state(
+                        job_id,
+                        format!("Job is already running: {job_id}"),
+                    ));
+                }
+                job.pause_flag.store(false, Ordering::Release);
+                job.state.status = JobStatus::Running;
+                self.persist(job, true);
+                return Ok(false);
+            }
+
+            if !has_unfinished_files(&job.state) {
+                return Err(AppError::invalid_job_state(
+                    job_id,
+                    format!("Job has no unfinished files: {job_id}"),
+                ));
+            }
+
+            reset_interrupted_files(&mut job.state);
+            job.state.status = JobStatus::Pending;
+            job.state.completed_at_ms = None;
+            job.cancel_flag = Arc::new(AtomicBool::new(false));
+            job.pause_flag = Arc::new(AtomicBool::new(false));
+            job.active = true;
+            queue.enqueue(job_id, job.request.priority());
+            self.persist(job, true);
+            Ok(true)
+        })?;
+        self.slot_notify.notify_waiters();
+        resumed
+    }
+
+    pub fn pause_job(&self, job_id: &str) -> Result<(), AppError> {
+        self.with_job(job_id, |job| {
+            if !matches!(job.state.status, JobStatus::Pending | JobStatus::Running) {
+                return Err(AppError::invalid_job_state(
+                    job_id,
+                    format!("Job is not running: {job_id}"),
+                ));
+            }
+            job.pause_flag.store(true, Ordering::Release);
+            job.state.status = JobStatus::Paused;
+            self.persist(job, true);
+            Ok(())
+        })??;
+        self.slot_notify.notify_waiters();
+        Ok(())
+    }
+
+    /// 执行协程在在途文件全部停下后调用：仍处于暂停则释放任务并返回 true，
+    /// 若期间已被恢复则返回 false，由协程继续处理剩余文件。
+    pub fn settle_pause(&self, job_id: &str) -> Result<bool, AppError> {
+        self.with_job_and_queue(job_id, |job, queue| {
+            if !job.pause_flag.load(Ordering::Acquire) {
+                return false;
+            }
+            job.state.status = JobStatus::Paused;
+            job.active = false;
+            queue.remove(job_id);
+            self.persist(job, true);
+            true
+        })
+    }
+
+    /// 等待一个全局文件槽位；任务被暂停或取消时返回 None。
+    /// 槽位按队列顺序分配，首次拿到槽位时任务进入 Running。
+    pub async fn acquire_file_slot(&self, job_id: &str) -> Option<FileSlot> {
+        loop {
+            let notified = self.slot_notify.notified();
+            tokio::pin!(notified);
+            // 先登记唤醒再检查条件，避免检查与等待之间漏掉通知。
+            notified.as_mut().enable();
+            {
+                let mut guard = self.inner.lock().ok()?;
+                let Inner { jobs, queue, .. } = &mut *guard;
+                let is_stopped = |id: &str| {
+                    jobs.get(id).is_none_or(|job| {
+                        job.cancel_flag.load(Ordering::Acquire)
+                            || job.pause_flag.load(Ordering::Acquire)
+                    })
+                };
+                if is_stopped(job_id) {
+                    queue.stop_waiting(job_id);
+                    drop(guard);
+                    self.slot_notify.notify_waiters();
+                    return None;
+                }
+                if queue.try_acquire(job_id, is_stopped) {
+                    let mut started_job = false;
+                    if let Some(job) = jobs.get_mut(job_id) {
+                        if matches!(job.state.status, JobStatus::Pending) {
+                            job.state.status = JobStatus::Running;
+                            job.state.started_at_ms.get_or_insert_with(now_ms);
+                            self.persist(job, true);
+                            started_job = true;
+                        }
+                    }
+                    drop(guard);
+                    // 仍有空闲槽位时让队列中的下一个任务继续尝试。
+                    self.slot_notify.notify_waiters();
+                    return Some(FileSlot {
+                        manager: self.clone(),
+                        started_job,
+                    });
+                }
+            }
+            notified.await;
+        }
+    }
+
+    pub fn move_job_to_front(&self, job_id: &str) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        if !guard.queue.move_to_front(job_id) {
+            return Err(AppError::invalid_job_state(
+                job_id,
+                format!("Job is not queued: {job_id}"),
+            ));
+        }
+        drop(guard);
+        self.slot_notify.notify_waiters();
+        Ok(())
+    }
+
+    pub fn reorder_queue(&self, job_ids: &[String]) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        guard.queue.reorder(job_ids);
+        drop(guard);
+        self.slot_notify.notify_waiters();
+        Ok(())
+    }
+
+    /// 调整所有任务合计的同时处理文件数上限。
+    pub fn set_max_concurrent_files(&self, limit: usize) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        guard.queue.set_max_file_slots(limit);
+        drop(guard);
+        self.slot_notify.notify_waiters();
+        Ok(())
+    }
+
+    pub fn progress_settings(&self) -> Result<ProgressSettings, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
+        Ok(guard.progress_settings.clone())
+    }
+
+    pub fn set_progress_settings(&self, settings: ProgressSettings) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        guard.progress_settings = settings;
+        Ok(())
+    }
+
+    pub fn get_request(&self, job_id: &str) -> Result<JobRequest, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
         guard
             .jobs
             .get(job_id)
             .map(|j| j.request.clone())
-            .ok_or_else(|| format!("Job not found: {job_id}"))
+            .ok_or_else(|| AppError::job_not_found(job_id))
     }
 
-    pub fn list_jobs(&self) -> Result<Vec<ImageJobState>, String> {
-        let guard = self.inner.lock().map_err(|_| "Job manager poisoned")?;
+    /// 基于已结束任务构造重试请求：沿用原请求设置，输入仅保留失败或已取消的文件。
+    pub fn build_retry_request(&self, job_id: &str) -> Result<JobRequest, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
+        let job = guard
+            .jobs
+            .get(job_id)
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        if job.active {
+            return Err(AppError::invalid_job_state(
+                job_id,
+                format!("Job is still running: {job_id}"),
+            ));
+        }
+
+        let inputs = job
+            .state
+            .files
+            .iter()
+            .filter(|file| matches!(file.status, JobStatus::Failed | JobStatus::Cancelled))
+            .map(|file| file.input_path.clone())
+            .collect::<Vec<_>>();
+        if inputs.is_empty() {
+            return Err(AppError::invalid_job_state(
+                job_id,
+                format!("Job has no failed or cancelled files: {job_id}"),
+            ));
+        }
+
+        Ok(job.request.follow_up(inputs, job_id))
+    }
+
+    /// `include_files` 为 false 时省略逐文件状态，仅返回汇总信息。
+    pub fn list_jobs(&self, include_files: bool) -> Result<Vec<JobState>, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
         let mut jobs = guard
             .jobs
             .values()
-            .map(|job| job.state.clone())
+            .map(|job| {
+                let mut state = snapshot(&guard, job);
+                if !include_files {
+                    state.files = Vec::new();
+                }
+                state
+            })
             .collect::<Vec<_>>();
         jobs.sort_by_key(|j| j.created_at_ms);
         Ok(jobs)
     }
 
-    pub fn get_job(&self, job_id: &str) -> Result<ImageJobState, String> {
-        let guard = self.inner.lock().map_err(|_| "Job manager poisoned")?;
+    pub fn get_job(&self, job_id: &str) -> Result<JobState, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
         guard
             .jobs
             .get(job_id)
-            .map(|job| job.state.clone())
-            .ok_or_else(|| format!("Job not found: {job_id}"))
-    }
-
-    pub fn mark_running(&self, job_id: &str) -> Result<(), String> {
-        self.with_job(job_id, |job| {
-            job.state.status = JobStatus::Running;
-            job.state.started_at_ms = Some(now_ms());
-        })
+            .map(|job| snapshot(&guard, job))
+            .ok_or_else(|| AppError::job_not_found(job_id))
     }
 
+    /// 更新单文件状态，返回更新后的任务总进度，便于并发场景下直接用于事件派发。
     pub fn update_file(
         &self,
         job_id: &str,
@@ -121,8 +429,8 @@ impl JobManager {
         status: Option<JobStatus>,
         progress: Option<f32>,
         output_path: Option<String>,
-        error: Option<String>,
-    ) -> Result<(), String> {
+        error: Option<AppError>,
+    ) -> Result<f32, AppError> {
         self.with_job(job_id, |job| {
             if let Some(file) = job.state.files.get_mut(file_index) {
                 if let Some(s) = status {
@@ -139,81 +447,700 @@ impl JobManager {
                 }
             }
             recalc_state(&mut job.state);
+            if status.is_some_and(is_terminal) {
+                self.persist(job, false);
+            }
+            job.state.overall_progress
+        })
+    }
+
+    /// 一次加锁写入多个处理中文件的进度，返回更新后的任务总进度；已落定的文件不受影响。
+    pub fn update_files_progress(
+        &self,
+        job_id: &str,
+        updates: &[(usize, f32)],
+    ) -> Result<f32, AppError> {
+        self.with_job(job_id, |job| {
+            for (file_index, progress) in updates {
+                if let Some(file) = job
+                    .state
+                    .files
+                    .get_mut(*file_index)
+                    .filter(|file| file.status == JobStatus::Running)
+                {
+                    file.progress = progress.clamp(0.0, 100.0);
+                }
+            }
+            recalc_state(&mut job.state);
+            job.state.overall_progress
+        })
+    }
+
+    /// 开始处理文件前调用：仅 Pending 的文件会被标记为 Running，已被单独取消或跳过的返回 false。
+    pub fn start_file(&self, job_id: &str, file_index: usize) -> Result<bool, AppError> {
+        self.with_job(job_id, |job| {
+            let Some(file) = job.state.files.get_mut(file_index) else {
+                return false;
+            };
+            if !matches!(file.status, JobStatus::Pending) {
+                return false;
+            }
+            file.status = JobStatus::Running;
+            file.progress = 0.0;
+            recalc_state(&mut job.state);
+            true
+        })
+    }
+
+    /// 取消或跳过单个文件。未开始的文件直接落定并返回 true；
+    /// 处理中的文件在下一个阶段边界停下，由执行协程落定，返回 false。
+    pub fn stop_file(
+        &self,
+        job_id: &str,
+        file_index: usize,
+        status: JobStatus,
+    ) -> Result<bool, AppError> {
+        if !matches!(status, JobStatus::Cancelled | JobStatus::Skipped) {
+            return Err(AppError::invalid_input(format!(
+                "Files can only be cancelled or skipped, not {status:?}"
+            )));
+        }
+        let settled = self.with_job_and_queue(job_id, |job, queue| {
+            let Some(file) = job.state.files.get_mut(file_index) else {
+                return Err(AppError::invalid_input(format!(
+                    "File index out of range: {file_index}"
+                ))
+                .with_job(job_id));
+            };
+            match file.status {
+                JobStatus::Pending => {
+                    file.status = status;
+                    file.progress = 0.0;
+                    file.error = matches!(status, JobStatus::Cancelled).then(AppError::cancelled);
+                    recalc_state(&mut job.state);
+                    // 已暂停的任务若因此没有剩余文件，直接结束，不必再恢复。
+                    if !job.active && !has_unfinished_files(&job.state) {
+                        job.state.status =
+                            final_job_status(job.cancel_flag.load(Ordering::Acquire), &job.state);
+                        job.state.completed_at_ms = Some(now_ms());
+                        queue.remove(job_id);
+                    }
+                    self.persist(job, true);
+                    Ok(true)
+                }
+                JobStatus::Running => {
+                    job.stop_requests.insert(file_index, status);
+                    job.file_stop_flags[file_index].store(true, Ordering::Release);
+                    Ok(false)
+                }
+                _ => Err(AppError::invalid_job_state(
+                    job_id,
+                    format!("File is not pending or running: {file_index}"),
+                )),
+            }
+        })??;
+        if settled {
+            self.apply_retention();
+        }
+        Ok(settled)
+    }
+
+    /// 取出处理中文件的停止请求（若有），同时清除其停止标记。
+    pub fn take_file_stop(
+        &self,
+        job_id: &str,
+        file_index: usize,
+    ) -> Result<Option<JobStatus>, AppError> {
+        self.with_job(job_id, |job| {
+            if let Some(flag) = job.file_stop_flags.get(file_index) {
+                flag.store(false, Ordering::Release);
+            }
+            job.stop_requests.remove(&file_index)
         })
     }
 
-    pub fn finish_job(&self, job_id: &str, status: JobStatus) -> Result<(), String> {
+    pub fn record_file_stats(
+        &self,
+        job_id: &str,
+        file_index: usize,
+        stats: JobFileStats,
+    ) -> Result<(), AppError> {
         self.with_job(job_id, |job| {
+            if let Some(file) = job.state.files.get_mut(file_index) {
+                file.stats = Some(stats);
+            }
+            recalc_state(&mut job.state);
+        })
+    }
+
+    pub fn finish_job(&self, job_id: &str, status: JobStatus) -> Result<(), AppError> {
+        self.with_job_and_queue(job_id, |job, queue| {
             job.state.status = status;
             job.state.completed_at_ms = Some(now_ms());
             if matches!(status, JobStatus::Completed) {
                 job.state.overall_progress = 100.0;
             }
-        })
+            job.active = false;
+            queue.remove(job_id);
+            self.persist(job, true);
+        })?;
+        self.apply_retention();
+        Ok(())
+    }
+
+    /// 删除任务记录。仍在执行的任务先取消，由执行协程在在途文件停下、发出结束事件后调用
+    /// `remove_deleted_job` 移除，此时返回 None；否则立即移除并返回删除前的快照。
+    pub fn delete_job(&self, job_id: &str) -> Result<Option<JobState>, AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let job = guard
+            .jobs
+            .get_mut(job_id)
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        job.cancel_flag.store(true, Ordering::Release);
+        if job.active {
+            job.delete_requested = true;
+            job.state.status = JobStatus::Cancelled;
+            drop(guard);
+            self.slot_notify.notify_waiters();
+            return Ok(None);
+        }
+
+        let job = guard
+            .jobs
+            .remove(job_id)
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        guard.queue.remove(job_id);
+        drop(guard);
+
+        self.remove_from_store(&[job_id.to_string()]);
+        self.slot_notify.notify_waiters();
+        Ok(Some(job.state))
+    }
+
+    /// 执行协程结束后调用：任务在执行中被删除时，此时才真正移除。
+    pub fn remove_deleted_job(&self, job_id: &str) {
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
+        };
+        if !guard
+            .jobs
+            .get(job_id)
+            .is_some_and(|job| job.delete_requested)
+        {
+            return;
+        }
+        guard.jobs.remove(job_id);
+        guard.queue.remove(job_id);
+        drop(guard);
+        self.remove_from_store(&[job_id.to_string()]);
+    }
+
+    /// 删除所有已结束（完成、失败或取消）的任务，返回删除数量。
+    pub fn clear_finished_jobs(&self) -> Result<usize, AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let finished = guard
+            .jobs
+            .iter()
+            .filter(|(_, job)| is_finished(job))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        for job_id in &finished {
+            guard.jobs.remove(job_id);
+        }
+        drop(guard);
+        self.remove_from_store(&finished);
+        Ok(finished.len())
+    }
+
+    pub fn retention_policy(&self) -> Result<RetentionPolicy, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
+        Ok(guard.retention.clone())
+    }
+
+    pub fn set_retention_policy(&self, policy: RetentionPolicy) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        guard.retention = policy.clone();
+        drop(guard);
+        if let Some(store) = &self.store {
+            store.save_retention(&policy)?;
+        }
+        self.apply_retention();
+        Ok(())
     }
 
-    pub fn cancel_job(&self, job_id: &str) -> Result<(), String> {
+    /// 按保留策略删除过期或超量的已结束任务，从最早结束的开始。
+    fn apply_retention(&self) {
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
+        };
+        let policy = guard.retention.clone();
+        let now = now_ms();
+
+        let mut finished = guard
+            .jobs
+            .values()
+            .filter(|job| is_finished(job))
+            .map(|job| {
+                let finished_at = job.state.completed_at_ms.unwrap_or(job.state.created_at_ms);
+                (finished_at, job.state.job_id.clone())
+            })
+            .collect::<Vec<_>>();
+        finished.sort();
+
+        let mut expired = Vec::new();
+        if let Some(max_age_ms) = policy.max_age_ms {
+            while let Some((finished_at, _)) = finished.first() {
+                if now.saturating_sub(*finished_at) <= max_age_ms {
+                    break;
+                }
+                expired.push(finished.remove(0).1);
+            }
+        }
+        if let Some(max_jobs) = policy.max_jobs {
+            let mut total = guard.jobs.len() - expired.len();
+            let mut oldest = finished.into_iter();
+            while total > max_jobs {
+                let Some((_, job_id)) = oldest.next() else {
+                    break;
+                };
+                expired.push(job_id);
+                total -= 1;
+            }
+        }
+
+        for job_id in &expired {
+            guard.jobs.remove(job_id);
+        }
+        drop(guard);
+        self.remove_from_store(&expired);
+    }
+
+    /// 退出前调用：补写执行中任务被节流的最新快照，并等待全部写入落盘。
+    pub fn flush_store(&self) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        if let Ok(mut guard) = self.inner.lock() {
+            for job in guard.jobs.values_mut().filter(|job| job.active) {
+                self.persist(job, true);
+            }
+        }
+        writer.flush();
+    }
+
+    fn remove_from_store(&self, job_ids: &[String]) {
+        if let Some(writer) = &self.writer {
+            for job_id in job_ids {
+                writer.remove(job_id);
+            }
+        }
+    }
+
+    pub fn cancel_job(&self, job_id: &str) -> Result<(), AppError> {
         self.with_job(job_id, |job| {
             job.cancel_flag.store(true, Ordering::Release);
-            if matches!(job.state.status, JobStatus::Pending | JobStatus::Running) {
+            if matches!(
+                job.state.status,
+                JobStatus::Pending | JobStatus::Running | JobStatus::Paused
+            ) {
                 job.state.status = JobStatus::Cancelled;
             }
-        })
+            // 没有执行协程（已暂停或重启后未恢复）时，由这里直接落定剩余文件。
+            if !job.active {
+                for file in &mut job.state.files {
+                    if matches!(file.status, JobStatus::Pending | JobStatus::Running) {
+                        file.status = JobStatus::Cancelled;
+                        file.progress = 0.0;
+                        file.error = Some(AppError::cancelled());
+                    }
+                }
+                recalc_state(&mut job.state);
+                job.state.completed_at_ms.get_or_insert_with(now_ms);
+            }
+            self.persist(job, true);
+        })?;
+        self.slot_notify.notify_waiters();
+        Ok(())
     }
 
-    pub fn cancel_flag(&self, job_id: &str) -> Result<Arc<AtomicBool>, String> {
-        let guard = self.inner.lock().map_err(|_| "Job manager poisoned")?;
+    pub fn cancel_flag(&self, job_id: &str) -> Result<Arc<AtomicBool>, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
         let job = guard
             .jobs
             .get(job_id)
-            .ok_or_else(|| format!("Job not found: {job_id}"))?;
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
         Ok(job.cancel_flag.clone())
     }
 
-    fn with_job<F>(&self, job_id: &str, mut f: F) -> Result<(), String>
+    pub fn pause_flag(&self, job_id: &str) -> Result<Arc<AtomicBool>, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
+        let job = guard
+            .jobs
+            .get(job_id)
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        Ok(job.pause_flag.clone())
+    }
+
+    pub fn file_stop_flags(&self, job_id: &str) -> Result<Arc<Vec<AtomicBool>>, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
+        let job = guard
+            .jobs
+            .get(job_id)
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        Ok(job.file_stop_flags.clone())
+    }
+
+    fn with_job<F, R>(&self, job_id: &str, f: F) -> Result<R, AppError>
     where
-        F: FnMut(&mut ManagedJob),
+        F: FnOnce(&mut ManagedJob) -> R,
     {
-        let mut guard = self.inner.lock().map_err(|_| "Job manager poisoned")?;
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
         let job = guard
             .jobs
             .get_mut(job_id)
-            .ok_or_else(|| format!("Job not found: {job_id}"))?;
-        f(job);
-        Ok(())
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        Ok(f(job))
+    }
+
+    fn with_job_and_queue<F, R>(&self, job_id: &str, f: F) -> Result<R, AppError>
+    where
+        F: FnOnce(&mut ManagedJob, &mut JobQueue) -> R,
+    {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let Inner { jobs, queue, .. } = &mut *guard;
+        let job = jobs
+            .get_mut(job_id)
+            .ok_or_else(|| AppError::job_not_found(job_id))?;
+        Ok(f(job, queue))
+    }
+
+    /// 在持有锁的情况下投递快照，保证同一任务的快照按顺序写入、不会被旧版本覆盖；
+    /// 实际写盘由后台线程完成。非强制写入按间隔节流。
+    fn persist(&self, job: &mut ManagedJob, force: bool) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let now = now_ms();
+        if !force && now.saturating_sub(job.last_persisted_ms) < PERSIST_INTERVAL_MS {
+            return;
+        }
+        job.last_persisted_ms = now;
+        writer.save(PersistedJob {
+            request: job.request.clone(),
+            state: job.state.clone(),
+        });
     }
 }
 
-fn recalc_state(state: &mut ImageJobState) {
+/// 未指定并发数时的默认值：CPU 逻辑核数。
+pub fn default_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// 对外快照附带排队位置：尚未开始的活动任务在队列中的名次（从 1 开始）。
+fn snapshot(inner: &Inner, job: &ManagedJob) -> JobState {
+    let mut state = job.state.clone();
+    state.queue_position = if matches!(state.status, JobStatus::Pending) {
+        inner
+            .queue
+            .job_ids()
+            .filter(|id| {
+                inner
+                    .jobs
+                    .get(*id)
+                    .is_some_and(|queued| matches!(queued.state.status, JobStatus::Pending))
+            })
+            .position(|id| id == state.job_id)
+            .map(|index| index + 1)
+    } else {
+        None
+    };
+    state
+}
+
+fn poisoned() -> AppError {
+    AppError::internal("Job manager poisoned")
+}
+
+fn is_finished(job: &ManagedJob) -> bool {
+    !job.active && is_terminal(job.state.status)
+}
+
+fn is_terminal(status: JobStatus) -> bool {
+    matches!(
+        status,
+        JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Skipped
+    )
+}
+
+fn has_unfinished_files(state: &JobState) -> bool {
+    state
+        .files
+        .iter()
+        .any(|file| matches!(file.status, JobStatus::Pending | JobStatus::Running))
+}
+
+/// 任务终态：只有整体取消才算 Cancelled；单独取消或跳过的文件不影响结果，其余按是否有失败文件判定。
+pub fn final_job_status(job_cancelled: bool, state: &JobState) -> JobStatus {
+    if job_cancelled {
+        JobStatus::Cancelled
+    } else if state.failed_files > 0 {
+        JobStatus::Failed
+    } else {
+        JobStatus::Completed
+    }
+}
+
+fn new_file_stop_flags(len: usize) -> Arc<Vec<AtomicBool>> {
+    Arc::new((0..len).map(|_| AtomicBool::new(false)).collect())
+}
+
+/// 中断时仍在处理的文件无法确定是否写完，统一回退为 Pending 重新处理。
+fn reset_interrupted_files(state: &mut JobState) {
+    for file in &mut state.files {
+        if matches!(file.status, JobStatus::Running) {
+            file.status = JobStatus::Pending;
+            file.progress = 0.0;
+        }
+    }
+    recalc_state(state);
+}
+
+fn job_id_prefix(kind: JobKind) -> &'static str {
+    match kind {
+        JobKind::Image => "img-job-",
+        JobKind::Video => "video-job-",
+    }
+}
+
+/// 各类任务共用一个编号序列。
+fn parse_job_number(job_id: &str) -> Option<u64> {
+    [JobKind::Image, JobKind::Video]
+        .into_iter()
+        .find_map(|kind| job_id.strip_prefix(job_id_prefix(kind)))?
+        .parse()
+        .ok()
+}
+
+fn recalc_state(state: &mut JobState) {
     let mut completed = 0usize;
     let mut failed = 0usize;
     let mut cancelled = 0usize;
-    let mut sum_progress = 0.0f32;
+    let mut skipped = 0usize;
+    let mut total_input_bytes = 0u64;
+    let mut total_output_bytes = 0u64;
+    let mut ratio_sum = 0.0f32;
+    let mut ratio_count = 0usize;
 
     for file in &state.files {
-        sum_progress += file.progress;
+        if let (JobStatus::Completed, Some(stats)) = (file.status, &file.stats) {
+            if let (Some(input), Some(output)) = (stats.input_size_bytes, stats.output_size_bytes) {
+                total_input_bytes += input;
+                total_output_bytes += output;
+            }
+            if let Some(ratio) = stats.compression_ratio {
+                ratio_sum += ratio;
+                ratio_count += 1;
+            }
+        }
         match file.status {
             JobStatus::Completed => completed += 1,
             JobStatus::Failed => failed += 1,
             JobStatus::Cancelled => cancelled += 1,
-            JobStatus::Pending | JobStatus::Running => {}
+            JobStatus::Skipped => skipped += 1,
+            JobStatus::Pending | JobStatus::Running | JobStatus::Paused => {}
         }
     }
 
     state.completed_files = completed;
     state.failed_files = failed;
     state.cancelled_files = cancelled;
-    state.overall_progress = if state.total_files == 0 {
-        100.0
-    } else {
-        (sum_progress / state.total_files as f32).clamp(0.0, 100.0)
-    };
+    state.skipped_files = skipped;
+    state.total_input_bytes = total_input_bytes;
+    state.total_output_bytes = total_output_bytes;
+    state.total_saved_bytes = total_input_bytes as i64 - total_output_bytes as i64;
+    state.average_compression_ratio = (ratio_count > 0).then(|| ratio_sum / ratio_count as f32);
+    state.overall_progress = calculate_job_overall_progress(&state.files, state.total_files);
+}
+
+/// 以全部文件进度的平均值作为任务总进度；文件并发执行、乱序完成时依然成立。
+fn calculate_job_overall_progress(files: &[JobFileState], total_files: usize) -> f32 {
+    if total_files == 0 {
+        return 100.0;
+    }
+    let sum_progress = files.iter().map(|file| file.progress).sum::<f32>();
+    (sum_progress / total_files as f32).clamp(0.0, 100.0)
 }
 
-fn now_ms() -> u64 {
+pub fn now_ms() -> u64 {
     match SystemTime::now().duration_since(UNIX_EPOCH) {
         Ok(duration) => duration.as_millis() as u64,
         Err(_) => 0,
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::types::ImageJobRequest;
+
+    fn image_request(count: usize) -> JobRequest {
+        JobRequest::Image(ImageJobRequest {
+            inputs: (0..count).map(|i| format!("/in/{i}.png")).collect(),
+            ..Default::default()
+        })
+    }
+
+    /// 创建一个已结束的任务，结束时间为 `completed_at_ms`。
+    fn finished_job(manager: &JobManager, completed_at_ms: u64) -> String {
+        let job_id = manager.create_job(image_request(1)).unwrap();
+        manager.finish_job(&job_id, JobStatus::Completed).unwrap();
+        manager
+            .with_job(&job_id, |job| {
+                job.state.completed_at_ms = Some(completed_at_ms)
+            })
+            .unwrap();
+        job_id
+    }
+
+    fn job_ids(manager: &JobManager) -> Vec<String> {
+        let mut ids = manager
+            .list_jobs(false)
+            .unwrap()
+            .into_iter()
+            .map(|state| state.job_id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn retention_by_count_drops_oldest_finished_jobs() {
+        let manager = JobManager::default();
+        let now = now_ms();
+        let oldest = finished_job(&manager, now - 3_000);
+        let newest = finished_job(&manager, now - 1_000);
+        let older = finished_job(&manager, now - 2_000);
+        let running = manager.create_job(image_request(1)).unwrap();
+
+        manager
+            .set_retention_policy(RetentionPolicy {
+                max_jobs: Some(2),
+                max_age_ms: None,
+            })
+            .unwrap();
+        assert_eq!(job_ids(&manager), [newest.clone(), running.clone()]);
+        assert!(!job_ids(&manager).contains(&oldest));
+        assert!(!job_ids(&manager).contains(&older));
+
+        // 执行中的任务即使超量也不删除。
+        manager
+            .set_retention_policy(RetentionPolicy {
+                max_jobs: Some(0),
+                max_age_ms: None,
+            })
+            .unwrap();
+        assert_eq!(job_ids(&manager), [running]);
+    }
+
+    #[test]
+    fn retention_by_age_drops_expired_jobs() {
+        let manager = JobManager::default();
+        let now = now_ms();
+        let expired = finished_job(&manager, now - 120_000);
+        let recent = finished_job(&manager, now - 1_000);
+
+        manager
+            .set_retention_policy(RetentionPolicy {
+                max_jobs: None,
+                max_age_ms: Some(60_000),
+            })
+            .unwrap();
+        assert_eq!(job_ids(&manager), [recent]);
+        assert!(manager.get_job(&expired).is_err());
+    }
+
+    #[test]
+    fn final_status_is_cancelled_only_when_the_job_was_cancelled() {
+        let manager = JobManager::default();
+        let job_id = manager.create_job(image_request(3)).unwrap();
+        let status = |cancelled| {
+            let state = manager.get_job(&job_id).unwrap();
+            final_job_status(cancelled, &state)
+        };
+        assert_eq!(status(false), JobStatus::Completed);
+
+        for (index, file_status) in [
+            JobStatus::Completed,
+            JobStatus::Cancelled,
+            JobStatus::Skipped,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            manager
+                .update_file(&job_id, index, Some(file_status), None, None, None)
+                .unwrap();
+        }
+        assert_eq!(status(false), JobStatus::Completed);
+        assert_eq!(status(true), JobStatus::Cancelled);
+
+        manager
+            .update_file(&job_id, 0, Some(JobStatus::Failed), None, None, None)
+            .unwrap();
+        assert_eq!(status(false), JobStatus::Failed);
+        assert_eq!(status(true), JobStatus::Cancelled);
+    }
+
+    #[test]
+    fn reloaded_store_marks_in_flight_files_interrupted() {
+        let root = std::env::temp_dir().join(format!("cruncher-job-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let manager = JobManager::with_store(JobStore::open(root.clone()).unwrap());
+        let job_id = manager.create_job(image_request(3)).unwrap();
+        assert!(manager.start_file(&job_id, 0).unwrap());
+        manager
+            .update_file(&job_id, 0, None, Some(40.0), None, None)
+            .unwrap();
+        assert!(manager.start_file(&job_id, 1).unwrap());
+        manager
+            .update_file(
+                &job_id,
+                1,
+                Some(JobStatus::Completed),
+                Some(100.0),
+                None,
+                None,
+            )
+            .unwrap();
+        manager.flush_store();
+        drop(manager);
+
+        let reloaded = JobManager::with_store(JobStore::open(root.clone()).unwrap());
+        let state = reloaded.get_job(&job_id).unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(state.status, JobStatus::Pending);
+        let files = state
+            .files
+            .iter()
+            .map(|file| (file.status, file.progress))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            [
+                (JobStatus::Pending, 0.0),
+                (JobStatus::Completed, 100.0),
+                (JobStatus::Pending, 0.0),
+            ]
+        );
+        assert_eq!(state.completed_files, 1);
+
+        // 编号从已有任务之后继续。
+        let next_id = reloaded.create_job(image_request(1)).unwrap();
+        assert_ne!(next_id, job_id);
+    }
+}
@@ -1,2 +1,9 @@
+pub mod estimate;
+pub mod executor;
+pub mod image_executor;
 pub mod manager;
+pub mod report;
+pub mod scheduler;
+pub mod store;
 pub mod types;
+pub mod video_executor;
@@ -0,0 +1,397 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+use crate::error::AppError;
+use crate::job::types::{JobFileState, JobState, JobStatus};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+/// 报告中的单行：一个输入文件的处理结果。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobReportRow {
+    pub input_path: String,
+    pub output_path: Option<String>,
+    pub status: JobStatus,
+    pub error: Option<String>,
+    pub input_size_bytes: Option<u64>,
+    pub output_size_bytes: Option<u64>,
+    pub output_width: Option<u32>,
+    pub output_height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobReport {
+    pub job_id: String,
+    pub status: JobStatus,
+    pub total_files: usize,
+    pub completed_files: usize,
+    pub failed_files: usize,
+    pub cancelled_files: usize,
+    pub skipped_files: usize,
+    pub total_input_bytes: u64,
+    pub total_output_bytes: u64,
+    pub total_saved_bytes: i64,
+    pub rows: Vec<JobReportRow>,
+}
+
+impl JobReport {
+    pub fn from_state(state: &JobState) -> Self {
+        Self {
+            job_id: state.job_id.clone(),
+            status: state.status,
+            total_files: state.total_files,
+            completed_files: state.completed_files,
+            failed_files: state.failed_files,
+            cancelled_files: state.cancelled_files,
+            skipped_files: state.skipped_files,
+            total_input_bytes: state.total_input_bytes,
+            total_output_bytes: state.total_output_bytes,
+            total_saved_bytes: state.total_saved_bytes,
+            rows: state.files.iter().map(report_row).collect(),
+        }
+    }
+}
+
+fn report_row(file: &JobFileState) -> JobReportRow {
+    let stats = file.stats.as_ref();
+    JobReportRow {
+        input_path: file.input_path.clone(),
+        output_path: file.output_path.clone(),
+        status: file.status,
+        error: file.error.as_ref().map(|e| e.message.clone()),
+        input_size_bytes: stats.and_then(|s| s.input_size_bytes),
+        output_size_bytes: stats.and_then(|s| s.output_size_bytes),
+        output_width: stats.map(|s| s.output_width),
+        output_height: stats.map(|s| s.output_height),
+    }
+}
+
+pub fn write_report(
+    state: &JobState,
+    format: ReportFormat,
+    output_path: &str,
+) -> Result<(), AppError> {
+    let report = JobReport::from_state(state);
+    let content = match format {
+        ReportFormat::Csv => render_csv(&report),
+        ReportFormat::Json => {
+            serde_json::to_string_pretty(&report).map_err(|e| AppError::internal(e.to_string()))?
+        }
+        ReportFormat::Html => render_html(&report),
+    };
+    std::fs::write(output_path, content).map_err(|e| AppError::io(e, output_path))
+}
+
+const COLUMNS: [&str; 8] = [
+    "input_path",
+    "output_path",
+    "status",
+    "error",
+    "input_size_bytes",
+    "output_size_bytes",
+    "output_width",
+    "output_height",
+];
+
+fn render_csv(report: &JobReport) -> String {
+    let mut out = COLUMNS.join(",");
+    out.push('\n');
+    for row in &report.rows {
+        let fields = [
+            row.input_path.clone(),
+            row.output_path.clone().unwrap_or_default(),
+            status_label(row.status).to_string(),
+            row.error.clone().unwrap_or_default(),
+            optional_number(row.input_size_bytes),
+            optional_number(row.output_size_bytes),
+            optional_number(row.output_width),
+            optional_number(row.output_height),
+        ];
+        let line = fields
+            .iter()
+            .map(|field| csv_escape(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// 含逗号、引号或换行的字段加双引号，内部引号双写。
+/// 以 `=`、`+`、`-`、`@` 开头的字段前加 `'`，避免在表格软件中被当作公式执行。
+fn csv_escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// 生成不依赖外部资源的单页 HTML：顶部汇总 + 可点击表头排序的明细表。
+fn render_html(report: &JobReport) -> String {
+    let mut rows = String::new();
+    for row in &report.rows {
+        let _ = writeln!(
+            rows,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td>{}{}<td data-value=\"{}\">{}</td></tr>",
+            html_escape(&row.input_path),
+            html_escape(row.output_path.as_deref().unwrap_or("")),
+            status_label(row.status),
+            html_escape(row.error.as_deref().unwrap_or("")),
+            size_cell(row.input_size_bytes),
+            size_cell(row.output_size_bytes),
+            row.output_width.unwrap_or(0) as u64 * row.output_height.unwrap_or(0) as u64,
+            match (row.output_width, row.output_height) {
+                (Some(w), Some(h)) => format!("{w}×{h}"),
+                _ => String::new(),
+            },
+        );
+    }
+
+    let saved_percent = if report.total_input_bytes > 0 {
+        report.total_saved_bytes as f64 / report.total_input_bytes as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Cruncher report – {job_id}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", sans-serif; margin: 32px; color: #1f2328; }}
+h1 {{ font-size: 20px; }}
+.totals {{ display: flex; gap: 24px; margin: 16px 0 24px; }}
+.totals div {{ background: #f6f8fa; border-radius: 8px; padding: 12px 16px; }}
+.totals b {{ display: block; font-size: 18px; }}
+table {{ border-collapse: collapse; width: 100%; font-size: 13px; }}
+th, td {{ border-bottom: 1px solid #d0d7de; padding: 6px 8px; text-align: left; }}
+th {{ cursor: pointer; user-select: none; background: #f6f8fa; }}
+th.asc::after {{ content: " ▲"; }}
+th.desc::after {{ content: " ▼"; }}
+td {{ word-break: break-all; }}
+</style>
+</head>
+<body>
+<h1>Job {job_id} ({status})</h1>
+<div class="totals">
+<div>Files<b>{completed} / {total}</b></div>
+<div>Failed<b>{failed}</b></div>
+<div>Cancelled<b>{cancelled}</b></div>
+<div>Skipped<b>{skipped}</b></div>
+<div>Before<b>{before}</b></div>
+<div>After<b>{after}</b></div>
+<div>Saved<b>{saved} ({saved_percent:.1}%)</b></div>
+</div>
+<table id="report">
+<thead><tr><th>Input</th><th>Output</th><th>Status</th><th>Error</th><th>Before</th><th>After</th><th>Dimensions</th></tr></thead>
+<tbody>
+{rows}</tbody>
+</table>
+<script>
+document.querySelectorAll("#report th").forEach(function (th, index) {{
+  th.addEventListener("click", function () {{
+    var tbody = document.querySelector("#report tbody");
+    var asc = !th.classList.contains("asc");
+    document.querySelectorAll("#report th").forEach(function (h) {{ h.classList.remove("asc", "desc"); }});
+    th.classList.add(asc ? "asc" : "desc");
+    var key = function (row) {{
+      var cell = row.children[index];
+      var value = cell.getAttribute("data-value");
+      return value === null ? cell.textContent : Number(value);
+    }};
+    Array.from(tbody.rows)
+      .sort(function (a, b) {{
+        var x = key(a), y = key(b);
+        var order = typeof x === "number" ? x - y : String(x).localeCompare(String(y));
+        return asc ? order : -order;
+      }})
+      .forEach(function (row) {{ tbody.appendChild(row); }});
+  }});
+}});
+</script>
+</body>
+</html>
+"##,
+        job_id = html_escape(&report.job_id),
+        status = status_label(report.status),
+        completed = report.completed_files,
+        total = report.total_files,
+        failed = report.failed_files,
+        cancelled = report.cancelled_files,
+        skipped = report.skipped_files,
+        before = format_bytes(report.total_input_bytes as i64),
+        after = format_bytes(report.total_output_bytes as i64),
+        saved = format_bytes(report.total_saved_bytes),
+        rows = rows,
+    )
+}
+
+fn size_cell(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) => format!(
+            "<td data-value=\"{bytes}\">{}</td>",
+            format_bytes(bytes as i64)
+        ),
+        None => "<td data-value=\"-1\"></td>".to_string(),
+    }
+}
+
+fn format_bytes(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "" };
+    let value = bytes.unsigned_abs() as f64;
+    if value >= 1024.0 * 1024.0 {
+        format!("{sign}{:.2} MB", value / (1024.0 * 1024.0))
+    } else if value >= 1024.0 {
+        format!("{sign}{:.1} KB", value / 1024.0)
+    } else {
+        format!("{sign}{value} B")
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn optional_number<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn status_label(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Pending => "pending",
+        JobStatus::Running => "running",
+        JobStatus::Paused => "paused",
+        JobStatus::Completed => "completed",
+        JobStatus::Failed => "failed",
+        JobStatus::Cancelled => "cancelled",
+        JobStatus::Skipped => "skipped",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_report() -> JobReport {
+        JobReport {
+            job_id: "img-job-7".to_string(),
+            status: JobStatus::Failed,
+            total_files: 2,
+            completed_files: 1,
+            failed_files: 1,
+            cancelled_files: 0,
+            skipped_files: 0,
+            total_input_bytes: 2048,
+            total_output_bytes: 1024,
+            total_saved_bytes: 1024,
+            rows: vec![
+                JobReportRow {
+                    input_path: "/photos/a, b.png".to_string(),
+                    output_path: Some("/out/a, b.png".to_string()),
+                    status: JobStatus::Completed,
+                    error: None,
+                    input_size_bytes: Some(2048),
+                    output_size_bytes: Some(1024),
+                    output_width: Some(800),
+                    output_height: Some(600),
+                },
+                JobReportRow {
+                    input_path: "/photos/=cmd.png".to_string(),
+                    output_path: None,
+                    status: JobStatus::Failed,
+                    error: Some("bad \"header\"\nline 2".to_string()),
+                    input_size_bytes: None,
+                    output_size_bytes: None,
+                    output_width: None,
+                    output_height: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn csv_escape_quotes_special_fields() {
+        assert_eq!(csv_escape("plain.png"), "plain.png");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_escape("cr\r"), "\"cr\r\"");
+        assert_eq!(csv_escape(""), "");
+    }
+
+    #[test]
+    fn csv_escape_neutralises_formulas() {
+        assert_eq!(csv_escape("=SUM(A1:A2)"), "'=SUM(A1:A2)");
+        assert_eq!(csv_escape("+1"), "'+1");
+        assert_eq!(csv_escape("-1"), "'-1");
+        assert_eq!(csv_escape("@cmd"), "'@cmd");
+        assert_eq!(
+            csv_escape("=HYPERLINK(\"x\",\"y\")"),
+            "\"'=HYPERLINK(\"\"x\"\",\"\"y\"\")\""
+        );
+        assert_eq!(csv_escape("a=b"), "a=b");
+    }
+
+    #[test]
+    fn html_escape_replaces_markup() {
+        assert_eq!(
+            html_escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!(html_escape("&lt;"), "&amp;lt;");
+        assert_eq!(html_escape("plain"), "plain");
+    }
+
+    #[test]
+    fn renders_csv() {
+        assert_eq!(
+            render_csv(&sample_report()),
+            "input_path,output_path,status,error,input_size_bytes,output_size_bytes,output_width,output_height\n\
+             \"/photos/a, b.png\",\"/out/a, b.png\",completed,,2048,1024,800,600\n\
+             /photos/=cmd.png,,failed,\"bad \"\"header\"\"\nline 2\",,,,\n"
+        );
+    }
+
+    #[test]
+    fn json_uses_camel_case_fields() {
+        let value = serde_json::to_value(sample_report()).unwrap();
+        assert_eq!(value["jobId"], "img-job-7");
+        assert_eq!(value["status"], "failed");
+        assert_eq!(value["totalSavedBytes"], 1024);
+        let row = &value["rows"][0];
+        assert_eq!(row["inputPath"], "/photos/a, b.png");
+        assert_eq!(row["outputSizeBytes"], 1024);
+        assert_eq!(row["outputWidth"], 800);
+        assert!(value["rows"][1]["outputPath"].is_null());
+        assert_eq!(value["rows"][1]["error"], "bad \"header\"\nline 2");
+    }
+
+    #[test]
+    fn html_escapes_paths() {
+        let mut report = sample_report();
+        report.rows[0].input_path = "<script>.png".to_string();
+        let html = render_html(&report);
+        assert!(html.contains("<td>&lt;script&gt;.png</td>"));
+        assert!(!html.contains("<script>.png"));
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+/// 跨任务的调度队列：按优先级（高者优先、同级先到先得）排列活动任务，
+/// 并限制所有任务同时处理的文件总数。本身不加锁，由 `JobManager` 持锁调用。
+pub struct JobQueue {
+    order: Vec<QueueEntry>,
+    waiting: HashSet<String>,
+    max_file_slots: usize,
+    slots_in_use: usize,
+}
+
+struct QueueEntry {
+    job_id: String,
+    priority: i32,
+}
+
+impl JobQueue {
+    pub fn new(max_file_slots: usize) -> Self {
+        Self {
+            order: Vec::new(),
+            waiting: HashSet::new(),
+            max_file_slots: max_file_slots.max(1),
+            slots_in_use: 0,
+        }
+    }
+
+    /// 插入到最后一个优先级不低于它的任务之后，保证同级 FIFO，且不打乱手动调整过的顺序。
+    pub fn enqueue(&mut self, job_id: &str, priority: i32) {
+        self.remove(job_id);
+        let index = self
+            .order
+            .iter()
+            .rposition(|entry| entry.priority >= priority)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        self.order.insert(
+            index,
+            QueueEntry {
+                job_id: job_id.to_string(),
+                priority,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, job_id: &str) {
+        self.order.retain(|entry| entry.job_id != job_id);
+        self.waiting.remove(job_id);
+    }
+
+    pub fn move_to_front(&mut self, job_id: &str) -> bool {
+        let Some(index) = self.order.iter().position(|entry| entry.job_id == job_id) else {
+            return false;
+        };
+        let entry = self.order.remove(index);
+        self.order.insert(0, entry);
+        true
+    }
+
+    /// 按给定顺序重排；未列出的任务保持原相对顺序排在其后。
+    pub fn reorder(&mut self, job_ids: &[String]) {
+        let mut reordered = Vec::with_capacity(self.order.len());
+        for job_id in job_ids {
+            if let Some(index) = self.order.iter().position(|entry| &entry.job_id == job_id) {
+                reordered.push(self.order.remove(index));
+            }
+        }
+        reordered.append(&mut self.order);
+        self.order = reordered;
+    }
+
+    pub fn job_ids(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(|entry| entry.job_id.as_str())
+    }
+
+    pub fn set_max_file_slots(&mut self, max_file_slots: usize) {
+        self.max_file_slots = max_file_slots.max(1);
+    }
+
+    /// 尝试为任务分配一个文件槽位：仅当有空闲槽位、且队列中排在它前面的等待任务都已停止时才成功。
+    /// 失败时任务被记为等待中，释放槽位后再重试。
+    pub fn try_acquire<S>(&mut self, job_id: &str, is_stopped: S) -> bool
+    where
+        S: Fn(&str) -> bool,
+    {
+        self.waiting.insert(job_id.to_string());
+        if self.slots_in_use >= self.max_file_slots {
+            return false;
+        }
+
+        let next = self
+            .order
+            .iter()
+            .map(|entry| entry.job_id.as_str())
+            .find(|id| self.waiting.contains(*id) && !is_stopped(id));
+        if next.is_some_and(|id| id != job_id) {
+            return false;
+        }
+
+        self.waiting.remove(job_id);
+        self.slots_in_use += 1;
+        true
+    }
+
+    pub fn stop_waiting(&mut self, job_id: &str) {
+        self.waiting.remove(job_id);
+    }
+
+    pub fn release(&mut self) {
+        self.slots_in_use = self.slots_in_use.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(queue: &JobQueue) -> Vec<&str> {
+        queue.job_ids().collect()
+    }
+
+    #[test]
+    fn enqueue_orders_by_priority_then_arrival() {
+        let mut queue = JobQueue::new(1);
+        queue.enqueue("a", 0);
+        queue.enqueue("b", 5);
+        queue.enqueue("c", 0);
+        queue.enqueue("d", 5);
+        queue.enqueue("e", -1);
+        assert_eq!(ids(&queue), ["b", "d", "a", "c", "e"]);
+
+        // 重新入队按新的优先级排在同级末尾。
+        queue.enqueue("a", 5);
+        assert_eq!(ids(&queue), ["b", "d", "a", "c", "e"]);
+        queue.enqueue("b", 0);
+        assert_eq!(ids(&queue), ["d", "a", "c", "b", "e"]);
+    }
+
+    #[test]
+    fn enqueue_keeps_manual_order() {
+        let mut queue = JobQueue::new(1);
+        queue.enqueue("a", 1);
+        queue.enqueue("b", 0);
+        assert!(queue.move_to_front("b"));
+        queue.enqueue("c", 0);
+        assert_eq!(ids(&queue), ["b", "a", "c"]);
+        assert!(!queue.move_to_front("missing"));
+    }
+
+    #[test]
+    fn reorder_puts_unlisted_jobs_after_listed_ones() {
+        let mut queue = JobQueue::new(1);
+        for id in ["a", "b", "c", "d"] {
+            queue.enqueue(id, 0);
+        }
+        queue.reorder(&["c".to_string(), "missing".to_string(), "a".to_string()]);
+        assert_eq!(ids(&queue), ["c", "a", "b", "d"]);
+    }
+
+    #[test]
+    fn try_acquire_respects_slot_limit() {
+        let mut queue = JobQueue::new(2);
+        queue.enqueue("a", 0);
+        assert!(queue.try_acquire("a", |_| false));
+        assert!(queue.try_acquire("a", |_| false));
+        assert!(!queue.try_acquire("a", |_| false));
+
+        queue.release();
+        assert!(queue.try_acquire("a", |_| false));
+
+        queue.set_max_file_slots(0);
+        queue.release();
+        queue.release();
+        assert!(queue.try_acquire("a", |_| false));
+        assert!(!queue.try_acquire("a", |_| false));
+    }
+
+    #[test]
+    fn try_acquire_serves_the_head_of_the_queue_first() {
+        let mut queue = JobQueue::new(1);
+        queue.enqueue("a", 0);
+        queue.enqueue("b", 0);
+        assert!(queue.try_acquire("a", |_| false));
+
+        // a 与 b 都在等待时，释放的槽位只交给排在前面的 a。
+        assert!(!queue.try_acquire("b", |_| false));
+        assert!(!queue.try_acquire("a", |_| false));
+        queue.release();
+        assert!(!queue.try_acquire("b", |_| false));
+        assert!(queue.try_acquire("a", |_| false));
+
+        // 排在前面的任务已停止或不再等待时不阻塞后面的任务。
+        queue.release();
+        assert!(queue.try_acquire("b", |id| id == "a"));
+        queue.release();
+        queue.stop_waiting("a");
+        assert!(queue.try_acquire("b", |_| false));
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+
+use crate::error::AppError;
+use crate::job::types::{JobRequest, JobState, RetentionPolicy};
+
+/// 落盘的任务记录：原始请求 + 含逐文件状态与时间戳的任务快照。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedJob {
+    pub request: JobRequest,
+    pub state: JobState,
+}
+
+/// 以「每个任务一个 JSON 文件」的方式持久化任务（`<root>/jobs/`），保留策略单独存放。
+pub struct JobStore {
+    dir: PathBuf,
+    retention_path: PathBuf,
+}
+
+impl JobStore {
+    pub fn open(root: PathBuf) -> Result<Self, AppError> {
+        let dir = root.join("jobs");
+        std::fs::create_dir_all(&dir).map_err(|e| AppError::io(e, &dir))?;
+        Ok(Self {
+            dir,
+            retention_path: root.join("job-retention.json"),
+        })
+    }
+
+    /// 读取全部任务记录；单个文件损坏时跳过，不影响其余任务恢复。
+    pub fn load_all(&self) -> Result<Vec<PersistedJob>, AppError> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| AppError::io(e, &self.dir))?;
+        let mut jobs = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| AppError::io(e, &self.dir))?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+            if let Ok(job) = serde_json::from_slice::<PersistedJob>(&bytes) {
+                jobs.push(job);
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// 先写临时文件再重命名，避免进程中途退出留下半截 JSON。
+    pub fn save(&self, job: &PersistedJob) -> Result<(), AppError> {
+        let path = self.job_path(&job.state.job_id);
+        let tmp_path = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(job).map_err(|e| AppError::internal(e.to_string()))?;
+        std::fs::write(&tmp_path, bytes).map_err(|e| AppError::io(e, &tmp_path))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| AppError::io(e, &path))
+    }
+
+    pub fn remove(&self, job_id: &str) -> Result<(), AppError> {
+        let path = self.job_path(job_id);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::io(e, &path)),
+        }
+    }
+
+    /// 未保存过或文件损坏时返回默认策略。
+    pub fn load_retention(&self) -> RetentionPolicy {
+        std::fs::read(&self.retention_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_retention(&self, policy: &RetentionPolicy) -> Result<(), AppError> {
+        let bytes =
+            serde_json::to_vec_pretty(policy).map_err(|e| AppError::internal(e.to_string()))?;
+        std::fs::write(&self.retention_path, bytes)
+            .map_err(|e| AppError::io(e, &self.retention_path))
+    }
+
+    fn job_path(&self, job_id: &str) -> PathBuf {
+        self.dir.join(format!("{job_id}.json"))
+    }
+}
+
+enum StoreOp {
+    Save(Box<PersistedJob>),
+    Remove(String),
+    /// 此前投递的写入全部完成后回复。
+    Flush(mpsc::Sender<()>),
+}
+
+/// 在后台线程中按提交顺序写盘，调用方可以在持锁时投递而不必等待磁盘 IO；
+/// 同一任务积压的多次写入只执行最后一次。
+pub struct JobStoreWriter {
+    sender: mpsc::Sender<StoreOp>,
+}
+
+impl JobStoreWriter {
+    pub fn spawn(store: Arc<JobStore>) -> Self {
+        let (sender, receiver) = mpsc::channel::<StoreOp>();
+        std::thread::spawn(move || {
+            while let Ok(op) = receiver.recv() {
+                let mut batch = vec![op];
+                batch.extend(receiver.try_iter());
+                write_batch(&store, batch);
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn save(&self, job: PersistedJob) {
+        let _ = self.sender.send(StoreOp::Save(Box::new(job)));
+    }
+
+    pub fn remove(&self, job_id: &str) {
+        let _ = self.sender.send(StoreOp::Remove(job_id.to_string()));
+    }
+
+    /// 阻塞到此前投递的写入全部落盘；应用退出前调用，避免最后的状态变更丢失。
+    pub fn flush(&self) {
+        let (ack, done) = mpsc::channel();
+        if self.sender.send(StoreOp::Flush(ack)).is_ok() {
+            let _ = done.recv();
+        }
+    }
+}
+
+fn write_batch(store: &JobStore, batch: Vec<StoreOp>) {
+    // 保存为 Some，删除为 None。
+    let mut writes = Vec::new();
+    let mut acks = Vec::new();
+    for op in batch {
+        match op {
+            StoreOp::Save(job) => writes.push((job.state.job_id.clone(), Some(job))),
+            StoreOp::Remove(job_id) => writes.push((job_id, None)),
+            StoreOp::Flush(ack) => acks.push(ack),
+        }
+    }
+    let last_write: HashMap<&str, usize> = writes
+        .iter()
+        .enumerate()
+        .map(|(index, (job_id, _))| (job_id.as_str(), index))
+        .collect();
+    for (index, (job_id, job)) in writes.iter().enumerate() {
+        if last_write.get(job_id.as_str()) != Some(&index) {
+            continue;
+        }
+        let _ = match job {
+            Some(job) => store.save(job),
+            None => store.remove(job_id),
+        };
+    }
+    for ack in acks {
+        let _ = ack.send(());
+    }
+}
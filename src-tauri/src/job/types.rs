@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::pipeline::stage::PipelineStageKind;
+use crate::error::AppError;
+use crate::pipeline::stage::PipelineStage;
 use crate::{CompressMode, CropRegion, ProcessOptions};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImageJobRequest {
     pub inputs: Vec<String>,
@@ -11,7 +12,113 @@ pub struct ImageJobRequest {
     pub mode: Option<CompressMode>,
     pub crop_region: Option<CropRegion>,
     pub options: Option<ProcessOptions>,
-    pub pipeline: Option<Vec<PipelineStageKind>>,
+    /// 阶段列表，可带各自参数；兼容旧版只有阶段名的写法。
+    #[serde(
+        default,
+        deserialize_with = "crate::pipeline::stage::deserialize_stages"
+    )]
+    pub pipeline: Option<Vec<PipelineStage>>,
+    pub max_concurrency: Option<usize>,
+    /// 调度优先级，数值越大越先处理；默认 0。
+    pub priority: Option<i32>,
+    /// 由 `retry_image_job` 创建时指向原任务；只在服务端设置，不接受调用方传入。
+    #[serde(skip_deserializing)]
+    pub parent_job_id: Option<String>,
+}
+
+/// 视频任务：逐个用 ffmpeg 转码，输出与输入同格式。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoJobRequest {
+    pub inputs: Vec<String>,
+    pub output_dir: Option<String>,
+    pub mode: Option<CompressMode>,
+    /// 默认 1：ffmpeg 自身已多线程编码。
+    pub max_concurrency: Option<usize>,
+    pub priority: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub parent_job_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    #[default]
+    Image,
+    Video,
+}
+
+/// 任务管理器中保存的请求，按 `kind` 区分媒体类型。
+/// 新增媒体类型时增加一个变体，并在 `job::executor::executor_for` 中接入对应的执行器。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum JobRequest {
+    Image(ImageJobRequest),
+    Video(VideoJobRequest),
+}
+
+impl JobRequest {
+    pub fn kind(&self) -> JobKind {
+        match self {
+            Self::Image(_) => JobKind::Image,
+            Self::Video(_) => JobKind::Video,
+        }
+    }
+
+    pub fn inputs(&self) -> &[String] {
+        match self {
+            Self::Image(request) => &request.inputs,
+            Self::Video(request) => &request.inputs,
+        }
+    }
+
+    pub fn priority(&self) -> i32 {
+        match self {
+            Self::Image(request) => request.priority,
+            Self::Video(request) => request.priority,
+        }
+        .unwrap_or(0)
+    }
+
+    pub fn max_concurrency(&self) -> Option<usize> {
+        match self {
+            Self::Image(request) => request.max_concurrency,
+            Self::Video(request) => request.max_concurrency,
+        }
+    }
+
+    pub fn parent_job_id(&self) -> Option<&str> {
+        match self {
+            Self::Image(request) => request.parent_job_id.as_deref(),
+            Self::Video(request) => request.parent_job_id.as_deref(),
+        }
+    }
+
+    /// 沿用原设置、只处理给定输入的后续任务请求。
+    pub fn follow_up(&self, inputs: Vec<String>, parent_job_id: &str) -> Self {
+        let parent_job_id = Some(parent_job_id.to_string());
+        match self {
+            Self::Image(request) => Self::Image(ImageJobRequest {
+                inputs,
+                parent_job_id,
+                ..request.clone()
+            }),
+            Self::Video(request) => Self::Video(VideoJobRequest {
+                inputs,
+                parent_job_id,
+                ..request.clone()
+            }),
+        }
+    }
+}
+
+/// 重试任务时可覆盖的参数；未提供的字段沿用原任务设置。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRetryOverrides {
+    pub output_dir: Option<String>,
+    pub mode: Option<CompressMode>,
+    pub options: Option<ProcessOptions>,
     pub max_concurrency: Option<usize>,
 }
 
@@ -20,9 +127,12 @@ pub struct ImageJobRequest {
 pub enum JobStatus {
     Pending,
     Running,
+    Paused,
     Completed,
     Failed,
     Cancelled,
+    /// 仅用于单个文件：被用户跳过，不计入失败或取消。
+    Skipped,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,14 +142,34 @@ pub struct JobFileState {
     pub output_path: Option<String>,
     pub status: JobStatus,
     pub progress: f32,
-    pub error: Option<String>,
+    pub error: Option<AppError>,
+    /// 文件处理完成后记录的体积、尺寸与耗时。
+    pub stats: Option<JobFileStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ImageJobState {
+pub struct JobFileStats {
+    pub input_size_bytes: Option<u64>,
+    pub output_size_bytes: Option<u64>,
+    /// 输出体积 / 输入体积，小于 1 表示变小。
+    pub compression_ratio: Option<f32>,
+    pub output_width: u32,
+    pub output_height: u32,
+    pub output_format: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobState {
     pub job_id: String,
+    #[serde(default)]
+    pub kind: JobKind,
+    pub parent_job_id: Option<String>,
     pub status: JobStatus,
+    /// 排队中（尚未开始）的任务在调度队列中的位置，从 1 开始。
+    pub queue_position: Option<usize>,
     pub created_at_ms: u64,
     pub started_at_ms: Option<u64>,
     pub completed_at_ms: Option<u64>,
@@ -47,7 +177,39 @@ pub struct ImageJobState {
     pub completed_files: usize,
     pub failed_files: usize,
     pub cancelled_files: usize,
+    #[serde(default)]
+    pub skipped_files: usize,
     pub overall_progress: f32,
+    /// 以下汇总仅统计已完成且有体积记录的文件。
+    #[serde(default)]
+    pub total_input_bytes: u64,
+    #[serde(default)]
+    pub total_output_bytes: u64,
+    /// 输入总量减输出总量，输出更大时为负数。
+    #[serde(default)]
+    pub total_saved_bytes: i64,
+    /// 各文件压缩比的平均值。
+    #[serde(default)]
+    pub average_compression_ratio: Option<f32>,
     pub files: Vec<JobFileState>,
 }
 
+/// 已结束任务的保留策略；`JobManager` 在新建/结束任务时自动清理超出的记录。
+/// 运行中、暂停或待恢复的任务不受影响。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// 最多保留的任务数，超出时从最早结束的任务开始删除。
+    pub max_jobs: Option<usize>,
+    /// 任务结束后最多保留的时长（毫秒）。
+    pub max_age_ms: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_jobs: Some(200),
+            max_age_ms: Some(30 * 24 * 60 * 60 * 1000),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+use std::time::Instant;
+
+use crate::core;
+use crate::error::AppError;
+use crate::job::executor::{build_file_stats, build_output_path, FileProgress, MediaExecutor};
+use crate::job::types::{JobFileStats, VideoJobRequest};
+use crate::CompressMode;
+
+/// 用 ffmpeg 逐文件转码视频，按已处理时长上报进度；停止时 ffmpeg 进程会被立即结束。
+pub struct VideoExecutor {
+    output_dir: Option<String>,
+    mode: CompressMode,
+}
+
+impl VideoExecutor {
+    pub fn new(request: &VideoJobRequest) -> Self {
+        Self {
+            output_dir: request.output_dir.clone(),
+            mode: request
+                .mode
+                .clone()
+                .unwrap_or(CompressMode::VisuallyLossless),
+        }
+    }
+}
+
+impl MediaExecutor for VideoExecutor {
+    /// ffmpeg 自身已多线程编码，默认一次只转码一个文件。
+    fn default_concurrency(&self) -> usize {
+        1
+    }
+
+    fn output_path(&self, input_path: &str) -> String {
+        build_output_path(
+            input_path,
+            self.output_dir.as_deref(),
+            &resolve_video_extension(input_path),
+        )
+    }
+
+    fn process_file(
+        &self,
+        input_path: &str,
+        output_path: &str,
+        on_progress: &mut dyn FnMut(FileProgress),
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<JobFileStats, AppError> {
+        let started = Instant::now();
+        let input_size_bytes = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let info = core::video::transcode_video(
+            input_path,
+            output_path,
+            &self.mode,
+            |progress| {
+                on_progress(FileProgress {
+                    stage: None,
+                    stage_progress: progress,
+                    file_progress: progress,
+                })
+            },
+            should_stop,
+        )?;
+
+        Ok(build_file_stats(
+            started,
+            input_size_bytes,
+            output_path,
+            info.width.unwrap_or(0),
+            info.height.unwrap_or(0),
+            resolve_video_extension(output_path),
+        ))
+    }
+}
+
+/// 视频输出沿用输入容器格式，无扩展名时输出 mp4。
+fn resolve_video_extension(input_path: &str) -> String {
+    Path::new(input_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_else(|| "mp4".to_string())
+}
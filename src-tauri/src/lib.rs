@@ -1,26 +1,51 @@
 //! Tauri commands for image/video compression tool.
 
 use serde::{Deserialize, Serialize};
-use tauri::ipc::Channel;
-use tauri::{AppHandle, Emitter, State};
+use tauri::ipc::{Channel, JavaScriptChannelId};
+use tauri::{AppHandle, Emitter, Manager, State, Webview};
 
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 
 mod core;
+mod error;
 mod job;
 mod pipeline;
+mod preset;
 mod progress;
+mod watch;
 
-use job::manager::JobManager;
-use job::types::{ImageJobRequest, ImageJobState, JobStatus};
-use pipeline::executor::execute_pipeline_for_file;
-use pipeline::stage::{stage_weight, PipelineStageKind};
-use pipeline::validator::{resolve_pipeline, validate_job_request, validate_pipeline};
-use progress::event::ImageJobProgressEvent;
+use core::adjust::AdjustParams;
+use error::AppError;
+use job::estimate::{estimate_job, ImageJobEstimate};
+use job::executor::{executor_for, FileProgress, MediaExecutor};
+use job::manager::{final_job_status, JobManager};
+use job::report::ReportFormat;
+use job::store::JobStore;
+use job::types::{
+    ImageJobRequest, JobFileStats, JobKind, JobRequest, JobRetryOverrides, JobState, JobStatus,
+    RetentionPolicy, VideoJobRequest,
+};
+use pipeline::stage::{PipelineStageKind, ResizeFilter};
+use pipeline::validator::{
+    resolve_pipeline, validate_job_inputs, validate_job_request, validate_pipeline,
+};
+use preset::store::PresetStore;
+use preset::types::{JobPreset, JobTemplate};
+use progress::event::{
+    JobChannelMessage, JobLifecycleEvent, JobLifecycleKind, JobProgressBatchEvent,
+    JobProgressEvent, WatchFolderErrorEvent, WatchFolderJobEvent,
+};
+use progress::subscription::JobSubscriptions;
+use progress::throttle::{ProgressSettings, ProgressThrottle};
+use watch::manager::{ReadyFile, WatchManager};
+use watch::store::WatchStore;
+use watch::types::{WatchFolder, WatchFolderRequest};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CropRegion {
     pub x: u32,
     pub y: u32,
@@ -28,22 +53,96 @@ pub struct CropRegion {
     pub height: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProcessOptions {
     pub quality: Option<u8>,
     pub format: Option<String>,
     pub width: Option<u32>,
     pub height: Option<u32>,
+    /// 解码时按 EXIF Orientation 摆正图片，默认开启。
+    pub auto_orient: Option<bool>,
+    /// 缺省为 `fit`：按宽高（只给一个时另一边不限）等比缩入。
+    pub resize_mode: Option<ResizeMode>,
+    /// `longestEdge` / `shortestEdge` 的目标边长。
+    pub resize_edge: Option<u32>,
+    /// `percentage` 的缩放百分比。
+    pub resize_percent: Option<f32>,
+    pub resize_filter: Option<ResizeFilter>,
+    /// 目标尺寸大于原图时不放大。
+    pub no_upscale: Option<bool>,
+    /// `pad` 的补边颜色（`#rrggbb[aa]`），默认透明。
+    pub resize_background: Option<String>,
+    /// 颜色调整；设置后自动流水线在缩放之后加入 Adjust 阶段。
+    pub adjust: Option<AdjustParams>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ProcessOptions {
+    /// 逐字段合并，`patch` 中已设置的字段覆盖当前值。
+    pub fn merge_from(&mut self, patch: ProcessOptions) {
+        if patch.quality.is_some() {
+            self.quality = patch.quality;
+        }
+        if patch.format.is_some() {
+            self.format = patch.format;
+        }
+        if patch.width.is_some() {
+            self.width = patch.width;
+        }
+        if patch.height.is_some() {
+            self.height = patch.height;
+        }
+        if patch.auto_orient.is_some() {
+            self.auto_orient = patch.auto_orient;
+        }
+        if patch.resize_mode.is_some() {
+            self.resize_mode = patch.resize_mode;
+        }
+        if patch.resize_edge.is_some() {
+            self.resize_edge = patch.resize_edge;
+        }
+        if patch.resize_percent.is_some() {
+            self.resize_percent = patch.resize_percent;
+        }
+        if patch.resize_filter.is_some() {
+            self.resize_filter = patch.resize_filter;
+        }
+        if patch.no_upscale.is_some() {
+            self.no_upscale = patch.no_upscale;
+        }
+        if patch.resize_background.is_some() {
+            self.resize_background = patch.resize_background;
+        }
+        if patch.adjust.is_some() {
+            self.adjust = patch.adjust;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum CompressMode {
     Lossless,
     VisuallyLossless,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResizeMode {
+    /// 拉伸到给定宽高；只给一边时另一边等比。
+    Exact,
+    /// 等比缩入宽高框内。
+    #[default]
+    Fit,
+    /// 等比铺满宽高框后居中裁掉多余部分。
+    Cover,
+    /// 等比缩入后居中补边到宽高框。
+    Pad,
+    LongestEdge,
+    ShortestEdge,
+    Percentage,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileInfo {
     pub path: String,
@@ -119,11 +218,12 @@ fn list_image_files_in_directory(dir: String) -> Result<Vec<String>, String> {
 /// Get file metadata: size, and for images dimensions and format.
 /// Runs in a blocking thread so the IPC thread is not blocked when loading many files.
 #[tauri::command]
-async fn get_file_info(path: String) -> Result<FileInfo, String> {
+async fn get_file_info(path: String) -> Result<FileInfo, AppError> {
     let path_clone = path.clone();
-    let meta = tauri::async_runtime::spawn_blocking(move || core::image::get_file_info(&path_clone))
-        .await
-        .map_err(|e| e.to_string())??;
+    let meta =
+        tauri::async_runtime::spawn_blocking(move || core::image::get_file_info(&path_clone))
+            .await
+            .map_err(|e| AppError::internal(e.to_string()))??;
 
     Ok(FileInfo {
         path,
@@ -144,7 +244,7 @@ async fn compress_image(
     crop_region: Option<CropRegion>,
     options: Option<ProcessOptions>,
     progress_callback: Channel<u8>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     tauri::async_runtime::spawn_blocking(move || {
         core::image::compress_image(
             &path,
@@ -158,7 +258,7 @@ async fn compress_image(
         )
     })
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| AppError::internal(e.to_string()))
     .flatten()
 }
 
@@ -168,53 +268,513 @@ async fn crop_image_command(
     input_path: String,
     output_path: String,
     options: CropOptions,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let output_path_for_task = output_path.clone();
     tauri::async_runtime::spawn_blocking(move || {
         core::image::perform_crop(&input_path, &output_path_for_task, &options)
     })
     .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| AppError::internal(e.to_string()))
     .flatten()?;
 
     Ok(output_path)
 }
 
+/// 指定 `preset_id` 时以预设为模板，请求中显式设置的字段优先。
+/// 传入 `on_event` 时，该任务的进度与生命周期事件（从 created 开始）也会推送到这个 Channel。
 #[tauri::command]
 async fn create_image_job(
     app: AppHandle,
+    webview: Webview,
     manager: State<'_, JobManager>,
+    presets: State<'_, PresetStore>,
     mut request: ImageJobRequest,
-) -> Result<String, String> {
-    validate_job_request(&request)?;
-    let stages = resolve_pipeline(&request);
-    validate_pipeline(&stages)?;
-    request.pipeline = Some(stages);
+    preset_id: Option<String>,
+    on_event: Option<JavaScriptChannelId>,
+) -> Result<String, AppError> {
+    apply_preset(&presets, preset_id.as_deref(), &mut request)?;
+    submit_job(
+        app,
+        manager.inner().clone(),
+        JobRequest::Image(request),
+        on_event.map(|id| id.channel_on(webview)),
+    )
+}
 
+/// 试运行：与 `create_image_job` 参数相同，在内存中编码以预测每个文件的输出体积、尺寸与节省量，
+/// 不写任何文件。指定 `sample_size` 时只随机估算这么多文件，汇总值按样本外推。
+#[tauri::command]
+async fn estimate_image_job(
+    presets: State<'_, PresetStore>,
+    mut request: ImageJobRequest,
+    preset_id: Option<String>,
+    sample_size: Option<usize>,
+) -> Result<ImageJobEstimate, AppError> {
+    if sample_size == Some(0) {
+        return Err(AppError::invalid_input("sample_size must be > 0"));
+    }
+    apply_preset(&presets, preset_id.as_deref(), &mut request)?;
+    prepare_image_job_request(&mut request)?;
+    tauri::async_runtime::spawn_blocking(move || estimate_job(&request, sample_size))
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))
+}
+
+fn apply_preset(
+    presets: &PresetStore,
+    preset_id: Option<&str>,
+    request: &mut ImageJobRequest,
+) -> Result<(), AppError> {
+    if let Some(preset_id) = preset_id {
+        presets.get(preset_id)?.template.apply_to(request);
+    }
+    Ok(())
+}
+
+/// 以原任务设置新建一个后续任务，仅包含原任务中失败或已取消的文件；可选覆盖部分参数。
+#[tauri::command]
+async fn retry_image_job(
+    app: AppHandle,
+    manager: State<'_, JobManager>,
+    job_id: String,
+    overrides: Option<JobRetryOverrides>,
+) -> Result<String, AppError> {
     let manager = manager.inner().clone();
+    let mut request = manager.build_retry_request(&job_id)?;
+    if let Some(overrides) = overrides {
+        match &mut request {
+            JobRequest::Image(request) => apply_retry_overrides(request, overrides),
+            JobRequest::Video(request) => apply_video_retry_overrides(request, overrides),
+        }
+    }
+    submit_job(app, manager, request, None)
+}
+
+/// 按 `kind` 新建任意媒体类型的任务；`create_image_job` 与 `create_video_job` 是它的特化形式。
+#[tauri::command]
+async fn create_job(
+    app: AppHandle,
+    webview: Webview,
+    manager: State<'_, JobManager>,
+    request: JobRequest,
+    on_event: Option<JavaScriptChannelId>,
+) -> Result<String, AppError> {
+    submit_job(
+        app,
+        manager.inner().clone(),
+        request,
+        on_event.map(|id| id.channel_on(webview)),
+    )
+}
+
+/// 校验请求后入队并启动执行；实际何时开始由全局调度决定。
+fn submit_job(
+    app: AppHandle,
+    manager: JobManager,
+    mut request: JobRequest,
+    channel: Option<Channel<JobChannelMessage>>,
+) -> Result<String, AppError> {
+    match &mut request {
+        JobRequest::Image(request) => prepare_image_job_request(request)?,
+        JobRequest::Video(request) => {
+            validate_job_inputs(&request.inputs, request.output_dir.as_deref())?
+        }
+    }
+    let kind = request.kind();
     let job_id = manager.create_job(request)?;
-    let manager_for_task = manager.clone();
-    let app_for_task = app.clone();
-    let job_id_for_task = job_id.clone();
-    tauri::async_runtime::spawn(async move {
-        run_image_job(app_for_task, manager_for_task, job_id_for_task).await;
-    });
+    if let Some(channel) = channel {
+        app.state::<JobSubscriptions>().register(&job_id, channel);
+    }
+    emit_lifecycle(
+        &app,
+        JobLifecycleEvent::new(
+            &job_id,
+            kind,
+            JobLifecycleKind::Created,
+            JobStatus::Pending,
+            0.0,
+        ),
+    );
+    spawn_job(app, manager, job_id.clone());
     Ok(job_id)
 }
 
+/// 先推送任务当前快照，再持续推送该任务之后的进度与生命周期事件，适合晚于任务创建打开的窗口。
+/// 已结束的任务只推送快照。
+#[tauri::command]
+fn subscribe_image_job(
+    manager: State<'_, JobManager>,
+    subscriptions: State<'_, JobSubscriptions>,
+    job_id: String,
+    on_event: Channel<JobChannelMessage>,
+) -> Result<(), AppError> {
+    subscriptions.subscribe(on_event, || manager.get_job(&job_id))
+}
+
+/// 新建视频压缩任务：与图片任务共用调度队列、暂停/取消、持久化、查询命令与 `job-progress` 事件。
+/// 默认一次只转码一个文件。
 #[tauri::command]
-fn cancel_image_job(manager: State<'_, JobManager>, job_id: String) -> Result<(), String> {
+async fn create_video_job(
+    app: AppHandle,
+    webview: Webview,
+    manager: State<'_, JobManager>,
+    request: VideoJobRequest,
+    on_event: Option<JavaScriptChannelId>,
+) -> Result<String, AppError> {
+    submit_job(
+        app,
+        manager.inner().clone(),
+        JobRequest::Video(request),
+        on_event.map(|id| id.channel_on(webview)),
+    )
+}
+
+/// 校验请求并解析出实际执行的流水线。
+fn prepare_image_job_request(request: &mut ImageJobRequest) -> Result<(), AppError> {
+    validate_job_request(request)?;
+    let stages = resolve_pipeline(request);
+    validate_pipeline(&stages)?;
+    request.pipeline = Some(stages);
+    Ok(())
+}
+
+/// 覆盖参数逐字段合并；若原流水线是按参数自动推导的，则按新参数重新推导。
+fn apply_retry_overrides(request: &mut ImageJobRequest, overrides: JobRetryOverrides) {
+    let auto_pipeline = request.pipeline.as_ref().is_none_or(|stages| {
+        let derived = resolve_pipeline(&ImageJobRequest {
+            pipeline: None,
+            ..request.clone()
+        });
+        *stages == derived
+    });
+
+    if let Some(output_dir) = overrides.output_dir {
+        request.output_dir = Some(output_dir);
+    }
+    if let Some(mode) = overrides.mode {
+        request.mode = Some(mode);
+    }
+    if let Some(max_concurrency) = overrides.max_concurrency {
+        request.max_concurrency = Some(max_concurrency);
+    }
+    if let Some(patch) = overrides.options {
+        request
+            .options
+            .get_or_insert_with(ProcessOptions::default)
+            .merge_from(patch);
+    }
+
+    if auto_pipeline {
+        request.pipeline = None;
+    }
+}
+
+/// 视频任务没有图片处理参数，只覆盖输出目录、模式与并发数。
+fn apply_video_retry_overrides(request: &mut VideoJobRequest, overrides: JobRetryOverrides) {
+    if let Some(output_dir) = overrides.output_dir {
+        request.output_dir = Some(output_dir);
+    }
+    if let Some(mode) = overrides.mode {
+        request.mode = Some(mode);
+    }
+    if let Some(max_concurrency) = overrides.max_concurrency {
+        request.max_concurrency = Some(max_concurrency);
+    }
+}
+
+/// 暂停任务：在途文件在下一个阶段边界停下，不再派发新文件。
+#[tauri::command]
+fn pause_image_job(manager: State<'_, JobManager>, job_id: String) -> Result<(), AppError> {
+    manager.pause_job(&job_id)
+}
+
+/// 继续执行已暂停或中断（如应用重启）的任务，只处理剩余的 Pending/Running 文件。
+#[tauri::command]
+fn resume_image_job(
+    app: AppHandle,
+    manager: State<'_, JobManager>,
+    job_id: String,
+) -> Result<(), AppError> {
+    let manager = manager.inner().clone();
+    if manager.resume_job(&job_id)? {
+        spawn_job(app, manager, job_id);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_image_job(manager: State<'_, JobManager>, job_id: String) -> Result<(), AppError> {
     manager.cancel_job(&job_id)
 }
 
+/// 取消任务中的单个文件，其余文件照常处理；处理中的文件在下一个阶段边界停下。
+#[tauri::command]
+fn cancel_image_job_file(
+    app: AppHandle,
+    manager: State<'_, JobManager>,
+    job_id: String,
+    file_index: usize,
+) -> Result<(), AppError> {
+    stop_image_job_file(&app, &manager, &job_id, file_index, JobStatus::Cancelled)
+}
+
+/// 跳过任务中的单个文件；与取消的区别仅在于不计入任务的取消数。
 #[tauri::command]
-fn get_image_job(manager: State<'_, JobManager>, job_id: String) -> Result<ImageJobState, String> {
+fn skip_image_job_file(
+    app: AppHandle,
+    manager: State<'_, JobManager>,
+    job_id: String,
+    file_index: usize,
+) -> Result<(), AppError> {
+    stop_image_job_file(&app, &manager, &job_id, file_index, JobStatus::Skipped)
+}
+
+/// 未开始的文件立即落定并发出事件；处理中的文件由执行协程在停下后发出。
+fn stop_image_job_file(
+    app: &AppHandle,
+    manager: &JobManager,
+    job_id: &str,
+    file_index: usize,
+    status: JobStatus,
+) -> Result<(), AppError> {
+    if !manager.stop_file(job_id, file_index, status)? {
+        return Ok(());
+    }
+    let state = manager.get_job(job_id)?;
+    let file = &state.files[file_index];
+    emit_progress(
+        app,
+        JobProgressEvent {
+            job_id: job_id.to_string(),
+            kind: state.kind,
+            file_index,
+            total_files: state.total_files,
+            input_path: Some(file.input_path.clone()),
+            output_path: None,
+            stage: None,
+            stage_progress: 0.0,
+            overall_progress: state.overall_progress,
+            status,
+            message: Some(file_stop_message(status).into()),
+            error: file.error.clone(),
+            stats: None,
+        },
+    );
+    emit_lifecycle(
+        app,
+        JobLifecycleEvent {
+            error: file.error.clone(),
+            ..JobLifecycleEvent::new(
+                job_id,
+                state.kind,
+                JobLifecycleKind::FileFinished,
+                status,
+                state.overall_progress,
+            )
+            .with_file(file_index, &file.input_path)
+        },
+    );
+    // 已暂停的任务因此没有剩余文件时已被直接结束。
+    if state.completed_at_ms.is_some() {
+        emit_job_finished(
+            app,
+            state.kind,
+            job_id,
+            state.total_files,
+            state.status,
+            state.overall_progress,
+        );
+    }
+    Ok(())
+}
+
+/// 将排队中的任务移到队首，下一个空闲槽位优先分配给它。
+#[tauri::command]
+fn move_image_job_to_front(manager: State<'_, JobManager>, job_id: String) -> Result<(), AppError> {
+    manager.move_job_to_front(&job_id)
+}
+
+/// 按给定顺序重排调度队列；未列出的任务保持原顺序排在其后。
+#[tauri::command]
+fn reorder_image_job_queue(
+    manager: State<'_, JobManager>,
+    job_ids: Vec<String>,
+) -> Result<(), AppError> {
+    manager.reorder_queue(&job_ids)
+}
+
+/// 设置所有任务合计同时处理的文件数上限。
+#[tauri::command]
+fn set_max_concurrent_files(manager: State<'_, JobManager>, limit: usize) -> Result<(), AppError> {
+    manager.set_max_concurrent_files(limit)
+}
+
+#[tauri::command]
+fn get_progress_settings(manager: State<'_, JobManager>) -> Result<ProgressSettings, AppError> {
+    manager.progress_settings()
+}
+
+/// 设置中间进度事件的节流与批量模式，对之后开始执行（含恢复）的任务生效。
+#[tauri::command]
+fn set_progress_settings(
+    manager: State<'_, JobManager>,
+    settings: ProgressSettings,
+) -> Result<(), AppError> {
+    manager.set_progress_settings(settings)
+}
+
+#[tauri::command]
+fn get_image_job(manager: State<'_, JobManager>, job_id: String) -> Result<JobState, AppError> {
     manager.get_job(&job_id)
 }
 
+/// `include_files` 默认为 true；批量任务较多时可传 false 只取汇总信息。
+#[tauri::command]
+fn list_image_jobs(
+    manager: State<'_, JobManager>,
+    include_files: Option<bool>,
+) -> Result<Vec<JobState>, AppError> {
+    manager.list_jobs(include_files.unwrap_or(true))
+}
+
+/// 删除任务记录；运行中的任务会先被取消，等在途文件停下并发出结束事件后再删除。
+#[tauri::command]
+fn delete_image_job(
+    app: AppHandle,
+    manager: State<'_, JobManager>,
+    job_id: String,
+) -> Result<(), AppError> {
+    let Some(state) = manager.delete_job(&job_id)? else {
+        return Ok(());
+    };
+    // 已暂停或中断后未恢复的任务没有执行协程，由这里补发结束事件并关闭订阅。
+    if matches!(
+        state.status,
+        JobStatus::Pending | JobStatus::Running | JobStatus::Paused
+    ) {
+        emit_job_finished(
+            &app,
+            state.kind,
+            &job_id,
+            state.total_files,
+            JobStatus::Cancelled,
+            state.overall_progress,
+        );
+    }
+    Ok(())
+}
+
+/// 删除所有已结束的任务，返回删除数量。
+#[tauri::command]
+fn clear_finished_jobs(manager: State<'_, JobManager>) -> Result<usize, AppError> {
+    manager.clear_finished_jobs()
+}
+
+#[tauri::command]
+fn get_job_retention_policy(manager: State<'_, JobManager>) -> Result<RetentionPolicy, AppError> {
+    manager.retention_policy()
+}
+
+#[tauri::command]
+fn set_job_retention_policy(
+    manager: State<'_, JobManager>,
+    policy: RetentionPolicy,
+) -> Result<(), AppError> {
+    manager.set_retention_policy(policy)
+}
+
+/// 将任务结果导出为 CSV、JSON 或独立 HTML 报告，每个文件一行；返回写入路径。
+#[tauri::command]
+fn export_job_report(
+    state: JobState,
+    format: ReportFormat,
+    output_path: String,
+) -> Result<String, AppError> {
+    job::report::write_report(&state, format, &output_path)?;
+    Ok(output_path)
+}
+
+/// 列出全部预设，内置预设在前。
+#[tauri::command]
+fn list_job_presets(presets: State<'_, PresetStore>) -> Result<Vec<JobPreset>, AppError> {
+    presets.list()
+}
+
+#[tauri::command]
+fn create_job_preset(
+    presets: State<'_, PresetStore>,
+    name: String,
+    template: JobTemplate,
+) -> Result<JobPreset, AppError> {
+    presets.create(&name, template)
+}
+
+/// 仅修改提供的字段；内置预设不可修改。
+#[tauri::command]
+fn update_job_preset(
+    presets: State<'_, PresetStore>,
+    id: String,
+    name: Option<String>,
+    template: Option<JobTemplate>,
+) -> Result<JobPreset, AppError> {
+    presets.update(&id, name, template)
+}
+
+#[tauri::command]
+fn delete_job_preset(presets: State<'_, PresetStore>, id: String) -> Result<(), AppError> {
+    presets.delete(&id)
+}
+
+/// 从 JSON 文件导入预设，返回新加入的预设。
+#[tauri::command]
+fn import_job_presets(
+    presets: State<'_, PresetStore>,
+    path: String,
+) -> Result<Vec<JobPreset>, AppError> {
+    presets.import(Path::new(&path))
+}
+
+/// 导出为 JSON 文件，可被 `import_job_presets` 读取；`ids` 为空时导出全部用户预设。
+#[tauri::command]
+fn export_job_presets(
+    presets: State<'_, PresetStore>,
+    path: String,
+    ids: Option<Vec<String>>,
+) -> Result<String, AppError> {
+    presets.export(Path::new(&path), ids.as_deref())?;
+    Ok(path)
+}
+
+/// 登记监听目录：其中新增或变更的图片稳定后，按预设各自创建一个任务，输出到 `output_dir`。
+#[tauri::command]
+fn add_watch_folder(
+    watches: State<'_, WatchManager>,
+    presets: State<'_, PresetStore>,
+    request: WatchFolderRequest,
+) -> Result<WatchFolder, AppError> {
+    presets.get(&request.preset_id)?;
+    watches.add_watch(request)
+}
+
+#[tauri::command]
+fn list_watch_folders(watches: State<'_, WatchManager>) -> Result<Vec<WatchFolder>, AppError> {
+    watches.list_watches()
+}
+
+#[tauri::command]
+fn pause_watch_folder(watches: State<'_, WatchManager>, watch_id: String) -> Result<(), AppError> {
+    watches.set_paused(&watch_id, true)
+}
+
+#[tauri::command]
+fn resume_watch_folder(watches: State<'_, WatchManager>, watch_id: String) -> Result<(), AppError> {
+    watches.set_paused(&watch_id, false)
+}
+
+/// 删除监听及其已处理记录；已创建的任务不受影响。
 #[tauri::command]
-fn list_image_jobs(manager: State<'_, JobManager>) -> Result<Vec<ImageJobState>, String> {
-    manager.list_jobs()
+fn remove_watch_folder(watches: State<'_, WatchManager>, watch_id: String) -> Result<(), AppError> {
+    watches.remove_watch(&watch_id)
 }
 
 /// Check if FFmpeg is available on the system.
@@ -258,116 +818,238 @@ fn open_folder(path: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// 执行整批图片任务：负责调度、取消、状态汇总与事件派发。
-async fn run_image_job(app: AppHandle, manager: JobManager, job_id: String) {
+/// 监听目录的轮询间隔；文件还需在此基础上稳定 `stable_ms` 才会处理。
+const WATCH_POLL_INTERVAL_MS: u64 = 1000;
+
+/// 后台轮询所有监听目录，为每个稳定下来的新图片或变更图片各建一个任务。
+async fn run_watch_loop(app: AppHandle) {
+    loop {
+        tokio::time::sleep(Duration::from_millis(WATCH_POLL_INTERVAL_MS)).await;
+        let watches = app.state::<WatchManager>().inner().clone();
+        let Ok(scan) = tauri::async_runtime::spawn_blocking(move || watches.scan()).await else {
+            continue;
+        };
+        for (watch_id, error) in scan.errors {
+            let _ = app.emit(
+                "watch-folder-error",
+                WatchFolderErrorEvent { watch_id, error },
+            );
+        }
+        for file in scan.ready {
+            start_watch_job(&app, file);
+        }
+    }
+}
+
+fn start_watch_job(app: &AppHandle, file: ReadyFile) {
+    let mut request = ImageJobRequest {
+        inputs: vec![file.path.clone()],
+        output_dir: Some(file.output_dir.clone()),
+        ..ImageJobRequest::default()
+    };
+    let result = apply_preset(
+        &app.state::<PresetStore>(),
+        Some(&file.preset_id),
+        &mut request,
+    )
+    .and_then(|()| {
+        submit_job(
+            app.clone(),
+            app.state::<JobManager>().inner().clone(),
+            JobRequest::Image(request),
+            None,
+        )
+    });
+    if let Ok(job_id) = &result {
+        let _ = app.emit(
+            "watch-folder-job",
+            WatchFolderJobEvent {
+                watch_id: file.watch_id.clone(),
+                job_id: job_id.clone(),
+                input_path: file.path.clone(),
+            },
+        );
+    }
+    let _ = app
+        .state::<WatchManager>()
+        .mark_processed(&file, result.as_deref());
+}
+
+/// 单个任务内所有文件共享的只读上下文，避免在并发的文件任务间逐个传参。
+struct JobContext {
+    app: AppHandle,
+    manager: JobManager,
+    job_id: String,
+    kind: JobKind,
+    inputs: Vec<String>,
+    executor: Arc<dyn MediaExecutor>,
+    progress: ProgressThrottle<FileProgress>,
+    batched_progress: bool,
+    cancel_flag: Arc<AtomicBool>,
+    pause_flag: Arc<AtomicBool>,
+    file_stop_flags: Arc<Vec<AtomicBool>>,
+}
+
+impl JobContext {
+    /// 取消、暂停或单独停止该文件时，在途处理应尽快停下。
+    fn should_stop(&self, file_index: usize) -> bool {
+        self.cancel_flag.load(Ordering::Acquire)
+            || self.pause_flag.load(Ordering::Acquire)
+            || self.file_stop_flags[file_index].load(Ordering::Acquire)
+    }
+}
+
+fn spawn_job(app: AppHandle, manager: JobManager, job_id: String) {
+    tauri::async_runtime::spawn(async move {
+        run_job(app, manager, job_id).await;
+    });
+}
+
+/// 执行整批任务：负责调度、取消、暂停、状态汇总与事件派发，单文件处理交给对应媒体类型的执行器。
+/// 最多同时处理 `max_concurrency` 个文件（默认由执行器决定），文件可能乱序完成。
+/// 已处于终态的文件（恢复执行时）会被跳过。
+async fn run_job(app: AppHandle, manager: JobManager, job_id: String) {
     let request = match manager.get_request(&job_id) {
         Ok(request) => request,
         Err(_) => return,
     };
 
-    let stages = request
-        .pipeline
-        .clone()
-        .unwrap_or_else(|| vec![PipelineStageKind::Compress, PipelineStageKind::Save]);
-    let total_files = request.inputs.len();
+    let inputs = request.inputs().to_vec();
+    let total_files = inputs.len();
     if total_files == 0 {
         let _ = manager.finish_job(&job_id, JobStatus::Completed);
+        emit_job_finished(
+            &app,
+            request.kind(),
+            &job_id,
+            0,
+            JobStatus::Completed,
+            100.0,
+        );
+        manager.remove_deleted_job(&job_id);
         return;
     }
 
-    let mode = request
-        .mode
-        .clone()
-        .unwrap_or(CompressMode::VisuallyLossless);
-    let output_dir = request.output_dir.clone();
-    let cancel_flag = match manager.cancel_flag(&job_id) {
-        Ok(flag) => flag,
-        Err(_) => return,
+    let kind = request.kind();
+    let executor = executor_for(&request);
+    let max_concurrency = request
+        .max_concurrency()
+        .filter(|n| *n > 0)
+        .unwrap_or_else(|| executor.default_concurrency());
+    let (cancel_flag, pause_flag) =
+        match (manager.cancel_flag(&job_id), manager.pause_flag(&job_id)) {
+            (Ok(cancel), Ok(pause)) => (cancel, pause),
+            _ => return,
+        };
+    let Ok(file_stop_flags) = manager.file_stop_flags(&job_id) else {
+        return;
     };
 
-    let _ = manager.mark_running(&job_id);
-
-    for (file_index, input_path) in request.inputs.iter().enumerate() {
-        if cancel_flag.load(Ordering::Acquire) {
-            let overall = calculate_file_base_progress(file_index, total_files);
-            mark_file_and_emit(
-                &app,
-                &manager,
-                &job_id,
-                file_index,
-                total_files,
-                input_path,
-                None,
-                &JobStatus::Cancelled,
-                0.0,
-                overall,
-                Some("Job cancelled".into()),
-                Some("Job cancelled".into()),
-            );
-            continue;
-        }
+    let progress_settings = manager.progress_settings().unwrap_or_default();
+    let ctx = Arc::new(JobContext {
+        app: app.clone(),
+        manager: manager.clone(),
+        job_id: job_id.clone(),
+        kind,
+        inputs: inputs.clone(),
+        executor,
+        progress: ProgressThrottle::new(progress_settings.max_events_per_second),
+        batched_progress: progress_settings.batched,
+        cancel_flag: cancel_flag.clone(),
+        pause_flag: pause_flag.clone(),
+        file_stop_flags,
+    });
+    let _trailing_flush = spawn_trailing_flush(ctx.clone());
 
-        let output_path = build_output_path(input_path, output_dir.as_deref(), request.options.as_ref());
-        let run_result = run_image_file_pipeline(
-            app.clone(),
-            manager.clone(),
-            job_id.clone(),
-            file_index,
-            total_files,
-            input_path.clone(),
-            output_path.clone(),
-            mode.clone(),
-            request.crop_region.clone(),
-            request.options.clone(),
-            stages.clone(),
-            cancel_flag.clone(),
-        )
-        .await;
+    loop {
+        let pending_indices = match manager.get_job(&job_id) {
+            Ok(state) => state
+                .files
+                .iter()
+                .enumerate()
+                .filter(|(_, file)| matches!(file.status, JobStatus::Pending | JobStatus::Running))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
 
-        match run_result {
-            Ok(()) => {
-                let overall = calculate_file_done_progress(file_index, total_files);
-                mark_file_and_emit(
+        // 先拿到任务内许可与全局槽位再派发，保证在途文件数既不超过本任务上限也不超过全局上限；
+        // 暂停后不再派发新文件。
+        let concurrency = resolve_max_concurrency(max_concurrency, pending_indices.len());
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let mut handles = Vec::with_capacity(pending_indices.len());
+        for file_index in pending_indices {
+            let Some(input_path) = inputs.get(file_index) else {
+                continue;
+            };
+            let permit = match semaphore.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            let slot = manager.acquire_file_slot(&job_id).await;
+            if slot.as_ref().is_some_and(|slot| slot.started_job) {
+                emit_lifecycle(
                     &app,
-                    &manager,
-                    &job_id,
-                    file_index,
-                    total_files,
-                    input_path,
-                    Some(output_path),
-                    &JobStatus::Completed,
-                    100.0,
-                    overall,
-                    Some("File completed".into()),
-                    None,
+                    JobLifecycleEvent::new(
+                        &job_id,
+                        kind,
+                        JobLifecycleKind::Started,
+                        JobStatus::Running,
+                        manager
+                            .get_job(&job_id)
+                            .map(|state| state.overall_progress)
+                            .unwrap_or(0.0),
+                    ),
                 );
             }
-            Err(err) => {
-                let cancelled = err.to_lowercase().contains("cancel");
-                let status = if cancelled {
-                    JobStatus::Cancelled
-                } else {
-                    JobStatus::Failed
-                };
-                let overall = calculate_file_base_progress(file_index, total_files);
-                mark_file_and_emit(
+            if pause_flag.load(Ordering::Acquire) && !cancel_flag.load(Ordering::Acquire) {
+                break;
+            }
+            let ctx = ctx.clone();
+            let input_path = input_path.clone();
+            handles.push(tauri::async_runtime::spawn(async move {
+                run_job_file(ctx, file_index, input_path).await;
+                drop(slot);
+                drop(permit);
+            }));
+        }
+        for handle in handles {
+            let _ = handle.await;
+        }
+
+        if cancel_flag.load(Ordering::Acquire) || !pause_flag.load(Ordering::Acquire) {
+            break;
+        }
+        // 等在途文件停下后再落定暂停；若这期间已被恢复，则继续处理剩余文件。
+        match manager.settle_pause(&job_id) {
+            Ok(true) => {
+                let overall = manager
+                    .get_job(&job_id)
+                    .map(|state| state.overall_progress)
+                    .unwrap_or(0.0);
+                emit_job_progress(
                     &app,
-                    &manager,
+                    kind,
                     &job_id,
-                    file_index,
                     total_files,
-                    input_path,
-                    None,
-                    &status,
-                    0.0,
+                    JobStatus::Paused,
                     overall,
-                    None,
-                    Some(err),
+                    "Job paused",
                 );
-                if cancelled {
-                    break;
-                }
+                emit_lifecycle(
+                    &app,
+                    JobLifecycleEvent::new(
+                        &job_id,
+                        kind,
+                        JobLifecycleKind::Paused,
+                        JobStatus::Paused,
+                        overall,
+                    ),
+                );
+                return;
             }
+            Ok(false) => continue,
+            Err(_) => return,
         }
     }
 
@@ -375,217 +1057,426 @@ async fn run_image_job(app: AppHandle, manager: JobManager, job_id: String) {
         Ok(snapshot) => snapshot,
         Err(_) => return,
     };
-    let final_status = resolve_final_job_status(&cancel_flag, &final_snapshot);
+    let final_status = final_job_status(cancel_flag.load(Ordering::Acquire), &final_snapshot);
 
-    let _ = manager.finish_job(&job_id, final_status.clone());
+    let _ = manager.finish_job(&job_id, final_status);
     let final_overall = manager
         .get_job(&job_id)
         .map(|state| state.overall_progress)
         .unwrap_or(100.0);
-    emit_progress(
+    emit_job_finished(
         &app,
-        ImageJobProgressEvent {
-            job_id,
-            file_index: total_files.saturating_sub(1),
-            total_files,
-            input_path: None,
-            output_path: None,
-            stage: None,
-            stage_progress: 100.0,
-            overall_progress: final_overall.clamp(0.0, 100.0),
-            status: final_status,
-            message: Some("Job finished".into()),
-            error: None,
-        },
+        kind,
+        &job_id,
+        total_files,
+        final_status,
+        final_overall,
     );
+    manager.remove_deleted_job(&job_id);
 }
 
-/// 执行单文件流水线，将阶段进度折算为文件/任务总进度。
-async fn run_image_file_pipeline(
-    app: AppHandle,
-    manager: JobManager,
-    job_id: String,
+/// 处理任务中的单个文件：检查取消/暂停、交给执行器处理并落定文件终态。
+async fn run_job_file(ctx: Arc<JobContext>, file_index: usize, input_path: String) {
+    if ctx.pause_flag.load(Ordering::Acquire) && !ctx.cancel_flag.load(Ordering::Acquire) {
+        return;
+    }
+    // 排队期间已被单独取消或跳过的文件不再处理。
+    if !matches!(ctx.manager.start_file(&ctx.job_id, file_index), Ok(true)) {
+        return;
+    }
+    emit_lifecycle(
+        &ctx.app,
+        JobLifecycleEvent::new(
+            &ctx.job_id,
+            ctx.kind,
+            JobLifecycleKind::FileStarted,
+            JobStatus::Running,
+            ctx.manager
+                .get_job(&ctx.job_id)
+                .map(|state| state.overall_progress)
+                .unwrap_or(0.0),
+        )
+        .with_file(file_index, &input_path),
+    );
+    if ctx.cancel_flag.load(Ordering::Acquire) {
+        mark_file_and_emit(
+            &ctx,
+            file_index,
+            &input_path,
+            None,
+            JobStatus::Cancelled,
+            0.0,
+            Some("Job cancelled".into()),
+            Some(AppError::cancelled()),
+            None,
+        );
+        return;
+    }
+
+    let output_path = ctx.executor.output_path(&input_path);
+    let run_result = process_job_file(
+        ctx.clone(),
+        file_index,
+        input_path.clone(),
+        output_path.clone(),
+    )
+    .await;
+    let stop_request = ctx
+        .manager
+        .take_file_stop(&ctx.job_id, file_index)
+        .ok()
+        .flatten();
+
+    match (run_result, stop_request) {
+        (Ok(stats), _) => {
+            mark_file_and_emit(
+                &ctx,
+                file_index,
+                &input_path,
+                Some(output_path),
+                JobStatus::Completed,
+                100.0,
+                Some("File completed".into()),
+                None,
+                Some(stats),
+            );
+        }
+        // 单独取消或跳过的文件在阶段边界停下，按请求落定，任务继续处理其余文件。
+        (Err(err), Some(status)) if err.is_cancelled() => {
+            mark_file_and_emit(
+                &ctx,
+                file_index,
+                &input_path,
+                None,
+                status,
+                0.0,
+                Some(file_stop_message(status).into()),
+                matches!(status, JobStatus::Cancelled).then(AppError::cancelled),
+                None,
+            );
+        }
+        // 暂停在阶段边界打断的文件回到 Pending，恢复后从头处理该文件；其它错误照常记为失败。
+        (Err(err), None)
+            if err.is_cancelled()
+                && ctx.pause_flag.load(Ordering::Acquire)
+                && !ctx.cancel_flag.load(Ordering::Acquire) =>
+        {
+            mark_file_and_emit(
+                &ctx,
+                file_index,
+                &input_path,
+                None,
+                JobStatus::Pending,
+                0.0,
+                Some("Job paused".into()),
+                None,
+                None,
+            );
+        }
+        (Err(err), _) => {
+            let status = if err.is_cancelled() {
+                JobStatus::Cancelled
+            } else {
+                JobStatus::Failed
+            };
+            mark_file_and_emit(
+                &ctx,
+                file_index,
+                &input_path,
+                None,
+                status,
+                0.0,
+                None,
+                Some(err),
+                None,
+            );
+        }
+    }
+}
+
+/// 在阻塞线程中执行单文件处理，中间进度经节流后发出；成功时返回体积、尺寸与耗时统计。
+async fn process_job_file(
+    ctx: Arc<JobContext>,
     file_index: usize,
-    total_files: usize,
     input_path: String,
     output_path: String,
-    mode: CompressMode,
-    crop_region: Option<CropRegion>,
-    options: Option<ProcessOptions>,
-    stages: Vec<PipelineStageKind>,
-    cancel_flag: Arc<AtomicBool>,
-) -> Result<(), String> {
-    let _ = manager.update_file(
-        &job_id,
-        file_index,
-        Some(JobStatus::Running),
-        Some(0.0),
-        None,
-        None,
-    );
-
-    let total_stage_weight = stages.iter().map(|s| stage_weight(*s)).sum::<f32>().max(1.0);
+) -> Result<JobFileStats, AppError> {
     tauri::async_runtime::spawn_blocking(move || {
-        let mut completed_weight = 0.0f32;
-        execute_pipeline_for_file(
+        ctx.executor.process_file(
             &input_path,
             &output_path,
-            &mode,
-            crop_region.as_ref(),
-            options.as_ref(),
-            &stages,
-            |stage, stage_progress| {
-                let stage_p = stage_progress.clamp(0.0, 100.0);
-                let stage_overall = ((completed_weight + stage_weight(stage) * (stage_p / 100.0))
-                    / total_stage_weight)
-                    * 100.0;
-                let job_overall =
-                    calculate_job_overall_progress(file_index, total_files, stage_overall);
-
-                let _ = manager.update_file(
-                    &job_id,
-                    file_index,
-                    Some(JobStatus::Running),
-                    Some(stage_overall),
-                    None,
-                    None,
-                );
-                emit_progress(
-                    &app,
-                    ImageJobProgressEvent {
-                        job_id: job_id.clone(),
-                        file_index,
-                        total_files,
-                        input_path: Some(input_path.clone()),
-                        output_path: None,
-                        stage: Some(stage),
-                        stage_progress: stage_p,
-                        overall_progress: job_overall,
-                        status: JobStatus::Running,
-                        message: None,
-                        error: None,
-                    },
-                );
-
-                if stage_p >= 100.0 {
-                    completed_weight += stage_weight(stage);
-                }
+            &mut |progress| {
+                ctx.progress.offer(file_index, progress, |updates| {
+                    emit_file_progress(&ctx, updates)
+                });
             },
-            || cancel_flag.load(Ordering::Acquire),
+            &|| ctx.should_stop(file_index),
         )
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// 任务执行期间按节流间隔补发积压的中间进度，避免长时间没有新进度时最后一次更新滞留；drop 时停止。
+struct TrailingFlush(tauri::async_runtime::JoinHandle<()>);
+
+impl Drop for TrailingFlush {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn spawn_trailing_flush(ctx: Arc<JobContext>) -> Option<TrailingFlush> {
+    let interval = ctx.progress.interval();
+    if interval.is_zero() {
+        return None;
+    }
+    Some(TrailingFlush(tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            ctx.progress
+                .flush_due(|updates| emit_file_progress(&ctx, updates));
+        }
+    })))
+}
+
+/// 发出节流后积攒的中间进度：先一次性写入管理器，总进度以管理器中的全部文件进度为准，
+/// 多个文件同时在途时也不会回退或跳变。非批量模式下只发出最近一次更新。
+fn emit_file_progress(ctx: &JobContext, mut updates: Vec<(usize, FileProgress)>) {
+    let file_progress = updates
+        .iter()
+        .map(|(file_index, progress)| (*file_index, progress.file_progress))
+        .collect::<Vec<_>>();
+    let overall_progress = ctx
+        .manager
+        .update_files_progress(&ctx.job_id, &file_progress)
+        .unwrap_or(0.0);
+    let to_event = |(file_index, progress): (usize, FileProgress)| JobProgressEvent {
+        job_id: ctx.job_id.clone(),
+        kind: ctx.kind,
+        file_index,
+        total_files: ctx.inputs.len(),
+        input_path: ctx.inputs.get(file_index).cloned(),
+        output_path: None,
+        stage: progress.stage,
+        stage_progress: progress.stage_progress,
+        overall_progress,
+        status: JobStatus::Running,
+        message: None,
+        error: None,
+        stats: None,
+    };
+
+    if ctx.batched_progress {
+        let event = JobProgressBatchEvent {
+            job_id: ctx.job_id.clone(),
+            kind: ctx.kind,
+            total_files: ctx.inputs.len(),
+            overall_progress,
+            updates: updates.into_iter().map(to_event).collect(),
+        };
+        ctx.app.state::<JobSubscriptions>().send(
+            &ctx.job_id,
+            &JobChannelMessage::ProgressBatch(event.clone()),
+        );
+        let _ = ctx.app.emit("job-progress-batch", event);
+    } else if let Some(latest) = updates.pop() {
+        emit_progress(&ctx.app, to_event(latest));
+    }
 }
 
-/// 更新单文件状态并发出对应进度事件。
+/// 更新单文件状态并发出对应进度事件；绕过节流，先发出积压的中间进度，终态事件排在其后。
+#[allow(clippy::too_many_arguments)]
 fn mark_file_and_emit(
-    app: &AppHandle,
-    manager: &JobManager,
-    job_id: &str,
+    ctx: &JobContext,
     file_index: usize,
-    total_files: usize,
     input_path: &str,
     output_path: Option<String>,
-    status: &JobStatus,
+    status: JobStatus,
     stage_progress: f32,
-    overall_progress: f32,
     message: Option<String>,
-    error: Option<String>,
+    error: Option<AppError>,
+    stats: Option<JobFileStats>,
 ) {
-    let _ = manager.update_file(
-        job_id,
-        file_index,
-        Some(status.clone()),
-        Some(stage_progress),
-        output_path.clone(),
-        error.clone(),
-    );
+    let flush_pending = |updates| emit_file_progress(ctx, updates);
+    ctx.progress.settle(flush_pending, || {
+        if let Some(stats) = stats.as_ref() {
+            let _ = ctx
+                .manager
+                .record_file_stats(&ctx.job_id, file_index, stats.clone());
+        }
+        let overall_progress = ctx
+            .manager
+            .update_file(
+                &ctx.job_id,
+                file_index,
+                Some(status),
+                Some(stage_progress),
+                output_path.clone(),
+                error.clone(),
+            )
+            .unwrap_or(0.0)
+            .clamp(0.0, 100.0);
 
-    emit_progress(
-        app,
-        ImageJobProgressEvent {
-            job_id: job_id.to_string(),
-            file_index,
-            total_files,
-            input_path: Some(input_path.to_string()),
-            output_path,
-            stage: if matches!(status, JobStatus::Completed) {
-                Some(PipelineStageKind::Save)
-            } else {
-                None
+        // 暂停打断的文件回到 Pending，之后会重新开始，不算结束。
+        let finished = (status != JobStatus::Pending).then(|| JobLifecycleEvent {
+            output_path: output_path.clone(),
+            error: error.clone(),
+            ..JobLifecycleEvent::new(
+                &ctx.job_id,
+                ctx.kind,
+                JobLifecycleKind::FileFinished,
+                status,
+                overall_progress,
+            )
+            .with_file(file_index, input_path)
+        });
+        emit_progress(
+            &ctx.app,
+            JobProgressEvent {
+                job_id: ctx.job_id.clone(),
+                kind: ctx.kind,
+                file_index,
+                total_files: ctx.inputs.len(),
+                input_path: Some(input_path.to_string()),
+                output_path,
+                stage: if matches!(status, JobStatus::Completed) && ctx.kind == JobKind::Image {
+                    Some(PipelineStageKind::Save)
+                } else {
+                    None
+                },
+                stage_progress,
+                overall_progress,
+                status,
+                message,
+                error,
+                stats,
             },
-            stage_progress,
-            overall_progress: overall_progress.clamp(0.0, 100.0),
-            status: status.clone(),
-            message,
-            error,
-        },
-    );
+        );
+        if let Some(event) = finished {
+            emit_lifecycle(&ctx.app, event);
+        }
+    });
 }
 
-fn resolve_final_job_status(cancel_flag: &Arc<AtomicBool>, snapshot: &ImageJobState) -> JobStatus {
-    if cancel_flag.load(Ordering::Acquire) || snapshot.cancelled_files > 0 {
-        JobStatus::Cancelled
-    } else if snapshot.failed_files > 0 {
-        JobStatus::Failed
+fn file_stop_message(status: JobStatus) -> &'static str {
+    if matches!(status, JobStatus::Skipped) {
+        "File skipped"
     } else {
-        JobStatus::Completed
+        "File cancelled"
     }
 }
 
-fn calculate_job_overall_progress(file_index: usize, total_files: usize, stage_overall: f32) -> f32 {
-    ((file_index as f32 + stage_overall.clamp(0.0, 100.0) / 100.0) / total_files as f32) * 100.0
+/// 并发数不超过待处理文件数。
+fn resolve_max_concurrency(max_concurrency: usize, total_files: usize) -> usize {
+    max_concurrency.clamp(1, total_files.max(1))
 }
 
-fn calculate_file_base_progress(file_index: usize, total_files: usize) -> f32 {
-    (file_index as f32 / total_files as f32) * 100.0
+/// 发出任务级（非单文件）事件，如暂停与结束。
+fn emit_job_progress(
+    app: &AppHandle,
+    kind: JobKind,
+    job_id: &str,
+    total_files: usize,
+    status: JobStatus,
+    overall_progress: f32,
+    message: &str,
+) {
+    emit_progress(
+        app,
+        JobProgressEvent {
+            job_id: job_id.to_string(),
+            kind,
+            file_index: total_files.saturating_sub(1),
+            total_files,
+            input_path: None,
+            output_path: None,
+            stage: None,
+            stage_progress: 100.0,
+            overall_progress: overall_progress.clamp(0.0, 100.0),
+            status,
+            message: Some(message.to_string()),
+            error: None,
+            stats: None,
+        },
+    );
 }
 
-fn calculate_file_done_progress(file_index: usize, total_files: usize) -> f32 {
-    ((file_index + 1) as f32 / total_files as f32) * 100.0
+/// 全局广播，同时推送给订阅了该任务的 Channel。
+fn emit_progress(app: &AppHandle, event: JobProgressEvent) {
+    app.state::<JobSubscriptions>()
+        .send(&event.job_id, &JobChannelMessage::Progress(event.clone()));
+    let _ = app.emit("job-progress", event);
 }
 
-fn emit_progress(app: &AppHandle, event: ImageJobProgressEvent) {
-    let _ = app.emit("image-job-progress", event);
+/// 任务结束后释放该任务的 Channel。
+fn emit_lifecycle(app: &AppHandle, event: JobLifecycleEvent) {
+    let subscriptions = app.state::<JobSubscriptions>();
+    subscriptions.send(&event.job_id, &JobChannelMessage::Lifecycle(event.clone()));
+    if event.event == JobLifecycleKind::Finished {
+        subscriptions.close(&event.job_id);
+    }
+    let _ = app.emit("job-lifecycle", event);
 }
 
-fn build_output_path(
-    input_path: &str,
-    output_dir: Option<&str>,
-    options: Option<&ProcessOptions>,
-) -> String {
-    let input = Path::new(input_path);
-    let stem = input
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("image");
-    let ext = resolve_output_extension(input_path, options);
-    let file_name = format!("{stem}_compressed.{ext}");
+/// 发出任务结束的进度事件与生命周期事件。
+fn emit_job_finished(
+    app: &AppHandle,
+    kind: JobKind,
+    job_id: &str,
+    total_files: usize,
+    status: JobStatus,
+    overall_progress: f32,
+) {
+    emit_job_progress(
+        app,
+        kind,
+        job_id,
+        total_files,
+        status,
+        overall_progress,
+        "Job finished",
+    );
+    emit_lifecycle(
+        app,
+        JobLifecycleEvent::new(
+            job_id,
+            kind,
+            JobLifecycleKind::Finished,
+            status,
+            overall_progress.clamp(0.0, 100.0),
+        ),
+    );
+}
 
-    if let Some(dir) = output_dir {
-        return Path::new(dir)
-            .join(file_name)
-            .to_string_lossy()
-            .to_string();
-    }
+/// 任务记录保存在应用数据目录下；目录不可用时退化为纯内存管理。
+fn init_job_manager(app: &AppHandle) -> JobManager {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::internal(e.to_string()))
+        .and_then(JobStore::open)
+        .map(JobManager::with_store)
+        .unwrap_or_default()
+}
 
-    let parent = input
-        .parent()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("."));
-    parent.join(file_name).to_string_lossy().to_string()
+/// 预设保存在应用配置目录下；目录不可用时只提供内置预设和本次会话内的预设。
+fn init_preset_store(app: &AppHandle) -> PresetStore {
+    app.path()
+        .app_config_dir()
+        .map_err(|e| AppError::internal(e.to_string()))
+        .and_then(PresetStore::open)
+        .unwrap_or_default()
 }
 
-fn resolve_output_extension(input_path: &str, options: Option<&ProcessOptions>) -> String {
-    let normalized = core::image::resolve_output_format(input_path, options);
-    if normalized.is_empty() {
-        return "png".to_string();
-    }
-    match normalized.as_str() {
-        "jpeg" => "jpg".to_string(),
-        _ => normalized,
-    }
+/// 监听记录与任务记录同在应用数据目录下；目录不可用时监听只在本次运行内有效。
+fn init_watch_manager(app: &AppHandle) -> WatchManager {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::internal(e.to_string()))
+        .and_then(WatchStore::open)
+        .map(WatchManager::with_store)
+        .unwrap_or_default()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -595,7 +1486,14 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
-        .manage(JobManager::default())
+        .setup(|app| {
+            app.manage(JobSubscriptions::default());
+            app.manage(init_job_manager(app.handle()));
+            app.manage(init_preset_store(app.handle()));
+            app.manage(init_watch_manager(app.handle()));
+            tauri::async_runtime::spawn(run_watch_loop(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             pick_files,
             pick_directory,
@@ -604,14 +1502,49 @@ pub fn run() {
             crop_image_command,
             compress_image,
             create_image_job,
+            subscribe_image_job,
+            estimate_image_job,
+            retry_image_job,
             cancel_image_job,
+            cancel_image_job_file,
+            skip_image_job_file,
+            pause_image_job,
+            resume_image_job,
+            move_image_job_to_front,
+            reorder_image_job_queue,
+            set_max_concurrent_files,
+            get_progress_settings,
+            set_progress_settings,
             get_image_job,
             list_image_jobs,
+            delete_image_job,
+            clear_finished_jobs,
+            get_job_retention_policy,
+            set_job_retention_policy,
+            export_job_report,
+            list_job_presets,
+            create_job_preset,
+            update_job_preset,
+            delete_job_preset,
+            import_job_presets,
+            export_job_presets,
+            add_watch_folder,
+            list_watch_folders,
+            pause_watch_folder,
+            resume_watch_folder,
+            remove_watch_folder,
             check_ffmpeg,
             compress_video,
+            create_video_job,
+            create_job,
             copy_file,
             open_folder,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<JobManager>().flush_store();
+            }
+        });
 }
@@ -1,79 +1,379 @@
+use ::image::metadata::Orientation;
+use ::image::{DynamicImage, Rgba};
 use std::path::Path;
 
-use crate::core::image;
-use crate::pipeline::stage::PipelineStageKind;
-use crate::{CompressMode, CropRegion, ProcessOptions};
+use crate::core::image::ResizeSpec;
+use crate::core::transform::{
+    compose_orientation, parse_color, right_angle_orientation, Anchor, PadFill,
+};
+use crate::core::watermark::{WatermarkSource, WatermarkSpec};
+use crate::core::{adjust, exif, filter, image, transform, watermark};
+use crate::error::AppError;
+use crate::pipeline::stage::{FlipDirection, PipelineStage, PipelineStageKind};
+use crate::pipeline::validator::resolve_output_format;
+use crate::CompressMode;
 
+/// 流水线写出的结果图信息。
+pub struct PipelineOutput {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+}
+
+/// 按列表顺序执行各阶段，参数取自阶段自身（由 `resolve_pipeline` 补全）。
+/// Compress 阶段在内存中编码，Save 阶段只负责写盘；两者之间可以检查编码结果。
+/// 流水线不含 Compress 时由 Save 以默认参数补做编码。
+/// 只改变方向的 JPEG 走 `encode_lossless_jpeg`，不重新编码像素。
 pub fn execute_pipeline_for_file<F, C>(
     input_path: &str,
     output_path: &str,
-    mode: &CompressMode,
-    crop_region: Option<&CropRegion>,
-    options: Option<&ProcessOptions>,
-    stages: &[PipelineStageKind],
+    stages: &[PipelineStage],
+    auto_orient: bool,
     mut on_stage_progress: F,
     mut is_cancelled: C,
-) -> Result<(), String>
+) -> Result<PipelineOutput, AppError>
 where
     F: FnMut(PipelineStageKind, f32),
     C: FnMut() -> bool,
 {
     if is_cancelled() {
-        return Err("Job cancelled".into());
+        return Err(AppError::cancelled());
+    }
+
+    if let Some(lossless) = encode_lossless_jpeg(input_path, stages, auto_orient) {
+        for stage in stages {
+            let kind = stage.kind();
+            if is_cancelled() {
+                return Err(AppError::cancelled().with_stage(kind));
+            }
+            on_stage_progress(kind, 0.0);
+            if kind == PipelineStageKind::Save {
+                write_output(output_path, &lossless.data).map_err(|e| e.with_stage(kind))?;
+            }
+            on_stage_progress(kind, 100.0);
+        }
+        return Ok(PipelineOutput {
+            width: lossless.width,
+            height: lossless.height,
+            format: lossless.format,
+        });
     }
 
-    let mut img = image::load_image(input_path)?;
-    let mut format = image::resolve_output_format(input_path, options);
-    let quality = options.and_then(|opt| opt.quality);
+    let mut img = image::decode_image(input_path, auto_orient)?;
+    let mut format = image::resolve_output_format(input_path, None);
+    let mut encoded: Option<Vec<u8>> = None;
 
     for stage in stages {
+        let kind = stage.kind();
         if is_cancelled() {
-            return Err("Job cancelled".into());
+            return Err(AppError::cancelled().with_stage(kind));
         }
 
-        on_stage_progress(*stage, 0.0);
+        on_stage_progress(kind, 0.0);
         match stage {
-            PipelineStageKind::Crop => {
-                if let Some(region) = crop_region {
-                    on_stage_progress(*stage, 20.0);
-                    img = image::apply_crop(img, region)?;
-                }
-                on_stage_progress(*stage, 100.0);
-            }
-            PipelineStageKind::Resize => {
-                on_stage_progress(*stage, 20.0);
-                img = image::apply_resize(img, options);
-                on_stage_progress(*stage, 100.0);
+            PipelineStage::Compress { mode, quality } => {
+                let mode = mode.clone().unwrap_or(CompressMode::VisuallyLossless);
+                let buf = image::encode_image(&img, &format, &mode, *quality, |p| {
+                    on_stage_progress(kind, p.clamp(0.0, 99.0));
+                })
+                .map_err(|e| e.with_stage(kind).with_path(input_path))?;
+                encoded = Some(buf);
+                on_stage_progress(kind, 100.0);
             }
-            PipelineStageKind::Convert => {
-                on_stage_progress(*stage, 30.0);
-                format = image::resolve_output_format(input_path, options);
-                on_stage_progress(*stage, 100.0);
+            PipelineStage::Save => {
+                let buf = match encoded.take() {
+                    Some(buf) => buf,
+                    None => image::encode_image(
+                        &img,
+                        &format,
+                        &CompressMode::VisuallyLossless,
+                        None,
+                        |p| {
+                            on_stage_progress(kind, p.clamp(0.0, 100.0) * 0.8);
+                        },
+                    )
+                    .map_err(|e| e.with_stage(kind).with_path(input_path))?,
+                };
+                if is_cancelled() {
+                    return Err(AppError::cancelled().with_stage(kind));
+                }
+                write_output(output_path, &buf).map_err(|e| e.with_stage(kind))?;
+                on_stage_progress(kind, 100.0);
             }
-            PipelineStageKind::Compress => {
-                on_stage_progress(*stage, 100.0);
+            _ => {
+                on_stage_progress(kind, 20.0);
+                img = apply_transform_stage(stage, img, &mut format, &mut is_cancelled)?;
+                on_stage_progress(kind, 100.0);
             }
-            PipelineStageKind::Save => {
-                if let Some(parent) = Path::new(output_path).parent() {
-                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-                }
-                on_stage_progress(*stage, 15.0);
-                image::save_image_with_format_progress(
-                    &img,
-                    output_path,
-                    &format,
-                    mode,
-                    quality,
-                    |save_progress| {
-                        let stage_progress = 15.0 + (save_progress.clamp(0.0, 100.0) * 0.85);
-                        on_stage_progress(*stage, stage_progress);
+        }
+    }
+
+    Ok(PipelineOutput {
+        width: img.width(),
+        height: img.height(),
+        format,
+    })
+}
+
+/// 把编码结果写到输出路径，必要时创建目录。
+fn write_output(output_path: &str, buf: &[u8]) -> Result<(), AppError> {
+    if let Some(parent) = Path::new(output_path).parent() {
+        std::fs::create_dir_all(parent).map_err(|e| AppError::io(e, parent))?;
+    }
+    std::fs::write(output_path, buf).map_err(|e| AppError::io(e, output_path))
+}
+
+/// 试运行的预测结果：编码后的体积与尺寸，未写盘。
+pub struct PipelineEstimate {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    pub size_bytes: u64,
+}
+
+/// 与 `execute_pipeline_for_file` 执行相同的变换，但只在内存中编码，跳过 Save 阶段。
+pub fn estimate_pipeline_for_file(
+    input_path: &str,
+    stages: &[PipelineStage],
+    auto_orient: bool,
+) -> Result<PipelineEstimate, AppError> {
+    if let Some(lossless) = encode_lossless_jpeg(input_path, stages, auto_orient) {
+        return Ok(PipelineEstimate {
+            width: lossless.width,
+            height: lossless.height,
+            format: lossless.format,
+            size_bytes: lossless.data.len() as u64,
+        });
+    }
+
+    let mut img = image::decode_image(input_path, auto_orient)?;
+    let mut format = image::resolve_output_format(input_path, None);
+    let (mut mode, mut quality) = (CompressMode::VisuallyLossless, None);
+    for stage in stages {
+        if let PipelineStage::Compress {
+            mode: stage_mode,
+            quality: stage_quality,
+        } = stage
+        {
+            mode = stage_mode.clone().unwrap_or(mode);
+            quality = *stage_quality;
+        }
+        img = apply_transform_stage(stage, img, &mut format, &mut || false)?;
+    }
+
+    let buf = image::encode_image(&img, &format, &mode, quality, |_| {}).map_err(|e| {
+        e.with_stage(PipelineStageKind::Compress)
+            .with_path(input_path)
+    })?;
+    Ok(PipelineEstimate {
+        width: img.width(),
+        height: img.height(),
+        format,
+        size_bytes: buf.len() as u64,
+    })
+}
+
+/// 执行不涉及编码与写盘的变换阶段，Compress / Save 原样返回。
+fn apply_transform_stage(
+    stage: &PipelineStage,
+    img: DynamicImage,
+    format: &mut String,
+    is_cancelled: &mut dyn FnMut() -> bool,
+) -> Result<DynamicImage, AppError> {
+    match stage {
+        PipelineStage::Crop { region } => match region {
+            Some(region) => image::apply_crop(img, region).map_err(|e| e.with_stage(stage.kind())),
+            None => Ok(img),
+        },
+        PipelineStage::Rotate {
+            degrees,
+            background,
+        } => {
+            let background = background
+                .as_deref()
+                .and_then(parse_color)
+                .unwrap_or(Rgba([0, 0, 0, 0]));
+            Ok(transform::rotate(img, degrees.unwrap_or(0.0), background))
+        }
+        PipelineStage::Flip { direction } => Ok(match direction {
+            Some(FlipDirection::Horizontal) => img.fliph(),
+            Some(FlipDirection::Vertical) => img.flipv(),
+            None => img,
+        }),
+        PipelineStage::Resize {
+            mode,
+            width,
+            height,
+            edge,
+            percent,
+            filter,
+            no_upscale,
+            background,
+        } => {
+            let spec = ResizeSpec {
+                mode: mode.unwrap_or_default(),
+                width: *width,
+                height: *height,
+                edge: *edge,
+                percent: *percent,
+                no_upscale: no_upscale.unwrap_or(false),
+                background: background
+                    .as_deref()
+                    .and_then(parse_color)
+                    .unwrap_or(Rgba([0, 0, 0, 0])),
+                filter: filter.unwrap_or_default().into(),
+            };
+            Ok(image::resize_image(img, &spec))
+        }
+        PipelineStage::Pad {
+            width,
+            height,
+            aspect,
+            anchor,
+            background,
+        } => {
+            let canvas = transform::pad_canvas_size(
+                (img.width(), img.height()),
+                *width,
+                *height,
+                aspect.as_deref().and_then(transform::parse_aspect),
+            );
+            let fill = match background.as_deref() {
+                Some("blur") => PadFill::Blur,
+                other => PadFill::Color(
+                    other
+                        .and_then(parse_color)
+                        .unwrap_or(Rgba([255, 255, 255, 255])),
+                ),
+            };
+            Ok(transform::pad(
+                img,
+                canvas,
+                anchor.unwrap_or(Anchor::Center),
+                fill,
+            ))
+        }
+        PipelineStage::Adjust(params) => Ok(adjust::apply_adjust(img, params)),
+        PipelineStage::Filter(params) => {
+            filter::apply_filter(img, params, is_cancelled).map_err(|e| e.with_stage(stage.kind()))
+        }
+        PipelineStage::Watermark {
+            image: logo,
+            text,
+            font,
+            color,
+            anchor,
+            offset_x,
+            offset_y,
+            opacity,
+            scale,
+            tiled,
+        } => {
+            let (source, default_scale) = match (logo, text) {
+                (Some(path), _) => (WatermarkSource::Image(path.clone()), 0.2),
+                (None, Some(text)) => (
+                    WatermarkSource::Text {
+                        text: text.clone(),
+                        font: font.clone(),
+                        color: color
+                            .as_deref()
+                            .and_then(parse_color)
+                            .unwrap_or(Rgba([255, 255, 255, 255])),
                     },
-                )?;
-                on_stage_progress(*stage, 100.0);
+                    0.05,
+                ),
+                (None, None) => return Ok(img),
+            };
+            let spec = WatermarkSpec {
+                source,
+                anchor: anchor.unwrap_or(Anchor::BottomRight),
+                offset: (offset_x.unwrap_or(0), offset_y.unwrap_or(0)),
+                opacity: opacity.unwrap_or(0.5),
+                scale: scale.unwrap_or(default_scale),
+                tiled: tiled.unwrap_or(false),
+            };
+            watermark::apply_watermark(img, &spec).map_err(|e| e.with_stage(stage.kind()))
+        }
+        PipelineStage::Convert { format: target } => {
+            if let Some(target) = target {
+                *format = target.clone();
             }
+            Ok(img)
         }
+        PipelineStage::Compress { .. } | PipelineStage::Save => Ok(img),
     }
+}
 
-    Ok(())
+/// 无损改写方向后的 JPEG 字节与显示尺寸。
+struct LosslessJpeg {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: String,
 }
 
+/// JPEG 输入且输出仍为 JPEG、改动像素的阶段只有直角 Rotate / Flip、Compress 未指定质量时，
+/// 把方向合成进 EXIF Orientation 并保留原始压缩数据；其余情况返回 None，走常规解码流程。
+fn encode_lossless_jpeg(
+    input_path: &str,
+    stages: &[PipelineStage],
+    auto_orient: bool,
+) -> Option<LosslessJpeg> {
+    let format = resolve_output_format(input_path, stages);
+    if !matches!(format.as_str(), "jpg" | "jpeg") {
+        return None;
+    }
+
+    let mut transform = Orientation::NoTransforms;
+    let mut reoriented = false;
+    for stage in stages {
+        let step = match stage {
+            PipelineStage::Rotate {
+                degrees: Some(degrees),
+                ..
+            } => right_angle_orientation(*degrees)?,
+            PipelineStage::Flip {
+                direction: Some(FlipDirection::Horizontal),
+            } => Orientation::FlipHorizontal,
+            PipelineStage::Flip {
+                direction: Some(FlipDirection::Vertical),
+            } => Orientation::FlipVertical,
+            PipelineStage::Compress { quality: None, .. }
+            | PipelineStage::Convert { .. }
+            | PipelineStage::Save => continue,
+            _ => return None,
+        };
+        transform = compose_orientation(transform, step);
+        reoriented = true;
+    }
+    if !reoriented {
+        return None;
+    }
+
+    let source = std::fs::read(input_path).ok()?;
+    if !exif::is_jpeg(&source) {
+        return None;
+    }
+    let current = if auto_orient {
+        exif::jpeg_orientation(&source)
+    } else {
+        Orientation::NoTransforms
+    };
+    let orientation = compose_orientation(current, transform);
+    let data = exif::with_jpeg_orientation(&source, orientation)?;
+
+    let (raw_width, raw_height) = ::image::image_dimensions(input_path).ok()?;
+    let (width, height) = match orientation {
+        Orientation::Rotate90
+        | Orientation::Rotate270
+        | Orientation::Rotate90FlipH
+        | Orientation::Rotate270FlipH => (raw_height, raw_width),
+        _ => (raw_width, raw_height),
+    };
+    Some(LosslessJpeg {
+        data,
+        width,
+        height,
+        format,
+    })
+}
@@ -1,34 +1,235 @@
-use serde::{Deserialize, Serialize};
+use ::image::imageops::FilterType;
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::core::adjust::AdjustParams;
+use crate::core::filter::FilterParams;
+use crate::core::transform::Anchor;
+use crate::{CompressMode, CropRegion, ResizeMode};
+
+/// 阶段类型，用于进度事件与错误定位。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum PipelineStageKind {
     Crop,
+    Rotate,
+    Flip,
     Resize,
+    Pad,
+    Adjust,
+    Filter,
+    Watermark,
     Convert,
     Compress,
     Save,
 }
 
-impl PipelineStageKind {
-    pub fn order(self) -> u8 {
-        match self {
-            Self::Crop => 0,
-            Self::Resize => 1,
-            Self::Convert => 2,
-            Self::Compress => 3,
-            Self::Save => 4,
-        }
-    }
-}
-
 pub fn stage_weight(stage: PipelineStageKind) -> f32 {
     match stage {
         PipelineStageKind::Crop => 15.0,
+        PipelineStageKind::Rotate => 15.0,
+        PipelineStageKind::Flip => 10.0,
         PipelineStageKind::Resize => 20.0,
+        PipelineStageKind::Pad => 10.0,
+        PipelineStageKind::Adjust => 15.0,
+        PipelineStageKind::Filter => 20.0,
+        PipelineStageKind::Watermark => 15.0,
         PipelineStageKind::Convert => 15.0,
         PipelineStageKind::Compress => 35.0,
         PipelineStageKind::Save => 15.0,
     }
 }
 
+/// 流水线中的一个阶段及其参数。同一类阶段可以出现多次，按列表顺序执行。
+/// 参数缺省时由 `resolve_pipeline` 从请求级的 `crop_region` / `options` / `mode` 补全。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum PipelineStage {
+    Crop {
+        region: Option<CropRegion>,
+    },
+    /// 顺时针角度；非直角时画布扩大，角落用 `background`（`#rrggbb[aa]`，默认透明）填充。
+    Rotate {
+        degrees: Option<f32>,
+        background: Option<String>,
+    },
+    Flip {
+        direction: Option<FlipDirection>,
+    },
+    /// 参数含义同 `ProcessOptions` 中的 `resize*` 字段。
+    Resize {
+        mode: Option<ResizeMode>,
+        width: Option<u32>,
+        height: Option<u32>,
+        edge: Option<u32>,
+        percent: Option<f32>,
+        filter: Option<ResizeFilter>,
+        no_upscale: Option<bool>,
+        background: Option<String>,
+    },
+    /// 扩展画布到目标宽高或宽高比（`w:h`），二者择一；不裁剪原图内容。
+    /// `background` 为颜色、`transparent` 或 `blur`（模糊的拉伸原图），默认白色。
+    Pad {
+        width: Option<u32>,
+        height: Option<u32>,
+        aspect: Option<String>,
+        /// 默认居中。
+        anchor: Option<Anchor>,
+        background: Option<String>,
+    },
+    /// 未设置的参数取 `ProcessOptions::adjust` 中的同名项。
+    Adjust(AdjustParams),
+    /// 降噪、模糊与 USM 锐化，通常放在 Resize 之后弥补缩小带来的发软。
+    Filter(FilterParams),
+    /// `image`（PNG 等图片路径）与 `text` 二选一；必须位于所有 Resize 之后，保证水印观感大小一致。
+    Watermark {
+        image: Option<String>,
+        text: Option<String>,
+        /// TTF 路径，缺省用内置字体。
+        font: Option<String>,
+        /// 文字颜色，默认白色。
+        color: Option<String>,
+        anchor: Option<Anchor>,
+        offset_x: Option<i32>,
+        offset_y: Option<i32>,
+        /// 0–1，默认 0.5。
+        opacity: Option<f32>,
+        /// 相对短边的比例：图片水印的长边、文字水印的字号；默认图片 0.2、文字 0.05。
+        scale: Option<f32>,
+        tiled: Option<bool>,
+    },
+    Convert {
+        format: Option<String>,
+    },
+    Compress {
+        mode: Option<CompressMode>,
+        quality: Option<u8>,
+    },
+    Save,
+}
+
+impl PipelineStage {
+    pub fn kind(&self) -> PipelineStageKind {
+        match self {
+            Self::Crop { .. } => PipelineStageKind::Crop,
+            Self::Rotate { .. } => PipelineStageKind::Rotate,
+            Self::Flip { .. } => PipelineStageKind::Flip,
+            Self::Resize { .. } => PipelineStageKind::Resize,
+            Self::Pad { .. } => PipelineStageKind::Pad,
+            Self::Adjust(_) => PipelineStageKind::Adjust,
+            Self::Filter(_) => PipelineStageKind::Filter,
+            Self::Watermark { .. } => PipelineStageKind::Watermark,
+            Self::Convert { .. } => PipelineStageKind::Convert,
+            Self::Compress { .. } => PipelineStageKind::Compress,
+            Self::Save => PipelineStageKind::Save,
+        }
+    }
+}
+
+/// 只给出阶段类型时，参数全部留空等待补全。
+impl From<PipelineStageKind> for PipelineStage {
+    fn from(kind: PipelineStageKind) -> Self {
+        match kind {
+            PipelineStageKind::Crop => Self::Crop { region: None },
+            PipelineStageKind::Rotate => Self::Rotate {
+                degrees: None,
+                background: None,
+            },
+            PipelineStageKind::Flip => Self::Flip { direction: None },
+            PipelineStageKind::Resize => Self::Resize {
+                mode: None,
+                width: None,
+                height: None,
+                edge: None,
+                percent: None,
+                filter: None,
+                no_upscale: None,
+                background: None,
+            },
+            PipelineStageKind::Pad => Self::Pad {
+                width: None,
+                height: None,
+                aspect: None,
+                anchor: None,
+                background: None,
+            },
+            PipelineStageKind::Adjust => Self::Adjust(AdjustParams::default()),
+            PipelineStageKind::Filter => Self::Filter(FilterParams::default()),
+            PipelineStageKind::Watermark => Self::Watermark {
+                image: None,
+                text: None,
+                font: None,
+                color: None,
+                anchor: None,
+                offset_x: None,
+                offset_y: None,
+                opacity: None,
+                scale: None,
+                tiled: None,
+            },
+            PipelineStageKind::Convert => Self::Convert { format: None },
+            PipelineStageKind::Compress => Self::Compress {
+                mode: None,
+                quality: None,
+            },
+            PipelineStageKind::Save => Self::Save,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FlipDirection {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    #[default]
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// 流水线既可以写成带参数的阶段对象，也兼容旧版只有阶段名的字符串数组。
+pub fn deserialize_stages<'de, D>(deserializer: D) -> Result<Option<Vec<PipelineStage>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Kind(PipelineStageKind),
+        Stage(PipelineStage),
+    }
+
+    let stages = Option::<Vec<Repr>>::deserialize(deserializer)?;
+    Ok(stages.map(|stages| {
+        stages
+            .into_iter()
+            .map(|stage| match stage {
+                Repr::Kind(kind) => kind.into(),
+                Repr::Stage(stage) => stage,
+            })
+            .collect()
+    }))
+}
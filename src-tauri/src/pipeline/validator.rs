@@ -1,117 +1,872 @@
 use std::path::Path;
 
+use crate::core::adjust::AdjustParams;
+use crate::core::filter::FilterParams;
+use crate::core::transform::{parse_aspect, parse_color};
+use crate::error::{AppError, ErrorCode};
 use crate::job::types::ImageJobRequest;
-use crate::pipeline::stage::PipelineStageKind;
+use crate::pipeline::stage::{PipelineStage, PipelineStageKind};
+use crate::preset::types::JobTemplate;
+use crate::{CompressMode, ProcessOptions, ResizeMode};
 
-pub fn validate_job_request(request: &ImageJobRequest) -> Result<(), String> {
-    if request.inputs.is_empty() {
-        return Err("inputs must not be empty".into());
-    }
-
-    for input in &request.inputs {
-        if !Path::new(input).exists() {
-            return Err(format!("input file not found: {input}"));
-        }
-    }
+pub fn validate_job_request(request: &ImageJobRequest) -> Result<(), AppError> {
+    validate_job_inputs(&request.inputs, request.output_dir.as_deref())?;
+    validate_job_settings(request)
+}
 
-    if let Some(dir) = &request.output_dir {
-        let path = Path::new(dir);
-        if !path.exists() {
-            return Err(format!("output directory not found: {dir}"));
-        }
-        if !path.is_dir() {
-            return Err(format!("output path is not directory: {dir}"));
-        }
-    }
+/// 预设模板不含输入文件：保存前只校验参数与补全后的流水线，问题在保存时即可发现。
+pub fn validate_job_template(template: &JobTemplate) -> Result<(), AppError> {
+    let mut request = ImageJobRequest::default();
+    template.apply_to(&mut request);
+    validate_job_settings(&request)?;
+    validate_pipeline(&resolve_pipeline(&request))
+}
 
+/// 与输入文件无关的请求参数：压缩选项的取值范围与裁剪区域。
+fn validate_job_settings(request: &ImageJobRequest) -> Result<(), AppError> {
     if let Some(options) = &request.options {
         if let Some(quality) = options.quality {
             if !(1..=100).contains(&quality) {
-                return Err("quality must be in [1, 100]".into());
+                return Err(AppError::invalid_input("quality must be in [1, 100]"));
             }
         }
         if let Some(width) = options.width {
             if width == 0 {
-                return Err("width must be > 0".into());
+                return Err(AppError::invalid_input("width must be > 0"));
             }
         }
         if let Some(height) = options.height {
             if height == 0 {
-                return Err("height must be > 0".into());
+                return Err(AppError::invalid_input("height must be > 0"));
             }
         }
+        if let Some(adjust) = &options.adjust {
+            validate_adjust(adjust).map_err(AppError::invalid_input)?;
+        }
+        if has_resize_size(options) {
+            validate_resize(
+                options.resize_mode.unwrap_or_default(),
+                options.width,
+                options.height,
+                options.resize_edge,
+                options.resize_percent,
+                options.resize_background.as_deref(),
+            )
+            .map_err(AppError::invalid_input)?;
+        }
     }
 
     if let Some(crop) = &request.crop_region {
         if crop.width == 0 || crop.height == 0 {
-            return Err("crop width and height must be > 0".into());
+            return Err(AppError::invalid_input("crop width and height must be > 0"));
         }
     }
 
     Ok(())
 }
 
-pub fn resolve_pipeline(request: &ImageJobRequest) -> Vec<PipelineStageKind> {
-    if let Some(stages) = &request.pipeline {
-        return stages.clone();
+/// 图片与视频任务共用：输入非空且都存在，输出目录（如指定）存在且是目录。
+pub fn validate_job_inputs(inputs: &[String], output_dir: Option<&str>) -> Result<(), AppError> {
+    if inputs.is_empty() {
+        return Err(AppError::invalid_input("inputs must not be empty"));
+    }
+
+    for input in inputs {
+        if !Path::new(input).exists() {
+            return Err(AppError::new(
+                ErrorCode::FileNotFound,
+                format!("input file not found: {input}"),
+            )
+            .with_path(input));
+        }
     }
 
-    let mut stages = Vec::new();
-    if request.crop_region.is_some() {
-        stages.push(PipelineStageKind::Crop);
+    if let Some(dir) = output_dir {
+        let path = Path::new(dir);
+        if !path.exists() {
+            return Err(AppError::new(
+                ErrorCode::FileNotFound,
+                format!("output directory not found: {dir}"),
+            )
+            .with_path(dir));
+        }
+        if !path.is_dir() {
+            return Err(
+                AppError::invalid_input(format!("output path is not directory: {dir}"))
+                    .with_path(dir),
+            );
+        }
     }
-    if let Some(options) = &request.options {
-        if options.width.is_some() || options.height.is_some() {
-            stages.push(PipelineStageKind::Resize);
-        }
-        if options
-            .format
-            .as_ref()
-            .map(|format| {
-                let f = format.trim().to_lowercase();
-                !f.is_empty() && f != "auto"
-            })
-            .unwrap_or(false)
+    Ok(())
+}
+
+/// 未显式给出流水线时按请求参数推导；显式给出时补全各阶段缺省的参数（取请求级设置）。
+/// 请求指定了输出格式而流水线中没有 Convert 时，在编码前补一个 Convert；没有 Compress 时在 Save 前补上。
+pub fn resolve_pipeline(request: &ImageJobRequest) -> Vec<PipelineStage> {
+    let options = request.options.as_ref();
+    let requested_format = options
+        .and_then(|opts| opts.format.as_deref())
+        .and_then(normalize_format);
+
+    let mut stages = match &request.pipeline {
+        Some(stages) => stages.clone(),
+        None => {
+            let mut kinds = Vec::new();
+            if request.crop_region.is_some() {
+                kinds.push(PipelineStageKind::Crop);
+            }
+            if options.is_some_and(has_resize_size) {
+                kinds.push(PipelineStageKind::Resize);
+            }
+            if options
+                .and_then(|opts| opts.adjust.as_ref())
+                .is_some_and(|adjust| !adjust.is_identity())
+            {
+                kinds.push(PipelineStageKind::Adjust);
+            }
+            kinds.push(PipelineStageKind::Compress);
+            kinds.push(PipelineStageKind::Save);
+            kinds.into_iter().map(PipelineStage::from).collect()
+        }
+    };
+
+    let is_encode_stage = |stage: &PipelineStage| {
+        matches!(stage, PipelineStage::Compress { .. } | PipelineStage::Save)
+    };
+    if requested_format.is_some()
+        && !stages
+            .iter()
+            .any(|stage| matches!(stage, PipelineStage::Convert { .. }))
+    {
+        let index = stages
+            .iter()
+            .position(is_encode_stage)
+            .unwrap_or(stages.len());
+        stages.insert(index, PipelineStageKind::Convert.into());
+    }
+    if !stages
+        .iter()
+        .any(|stage| matches!(stage, PipelineStage::Compress { .. }))
+    {
+        if let Some(index) = stages
+            .iter()
+            .position(|stage| matches!(stage, PipelineStage::Save))
         {
-            stages.push(PipelineStageKind::Convert);
+            stages.insert(index, PipelineStageKind::Compress.into());
+        }
+    }
+
+    for stage in &mut stages {
+        match stage {
+            PipelineStage::Crop { region } => {
+                if region.is_none() {
+                    *region = request.crop_region.clone();
+                }
+            }
+            PipelineStage::Resize {
+                mode,
+                width,
+                height,
+                edge,
+                percent,
+                filter,
+                no_upscale,
+                background,
+            } => {
+                let Some(opts) = options else {
+                    continue;
+                };
+                // 尺寸与方式成组补全，避免把请求级的方式套到阶段自带的尺寸上。
+                if width.is_none() && height.is_none() && edge.is_none() && percent.is_none() {
+                    *mode = mode.or(opts.resize_mode);
+                    *width = opts.width;
+                    *height = opts.height;
+                    *edge = opts.resize_edge;
+                    *percent = opts.resize_percent;
+                }
+                *filter = filter.or(opts.resize_filter);
+                *no_upscale = no_upscale.or(opts.no_upscale);
+                if background.is_none() {
+                    *background = opts.resize_background.clone();
+                }
+            }
+            PipelineStage::Convert { format } => {
+                *format = format
+                    .as_deref()
+                    .and_then(normalize_format)
+                    .or_else(|| requested_format.clone());
+            }
+            PipelineStage::Compress { mode, quality } => {
+                if mode.is_none() {
+                    *mode = Some(
+                        request
+                            .mode
+                            .clone()
+                            .unwrap_or(CompressMode::VisuallyLossless),
+                    );
+                }
+                if quality.is_none() {
+                    *quality = options.and_then(|opts| opts.quality);
+                }
+            }
+            PipelineStage::Adjust(params) => {
+                if let Some(base) = options.and_then(|opts| opts.adjust.as_ref()) {
+                    params.fill_from(base);
+                }
+            }
+            PipelineStage::Rotate { .. }
+            | PipelineStage::Flip { .. }
+            | PipelineStage::Pad { .. }
+            | PipelineStage::Filter(_)
+            | PipelineStage::Watermark { .. }
+            | PipelineStage::Save => {}
         }
     }
-    stages.push(PipelineStageKind::Compress);
-    stages.push(PipelineStageKind::Save);
     stages
 }
 
-pub fn validate_pipeline(stages: &[PipelineStageKind]) -> Result<(), String> {
+/// 小写化；空串与 `auto` 视为未指定。
+fn normalize_format(format: &str) -> Option<String> {
+    let normalized = format.trim().to_lowercase();
+    (!normalized.is_empty() && normalized != "auto").then_some(normalized)
+}
+
+/// 按阶段语义校验补全后的流水线：参数齐全，Compress 至多一次且之后只能是 Save，Save 恰好一次且在最后。
+pub fn validate_pipeline(stages: &[PipelineStage]) -> Result<(), AppError> {
     if stages.is_empty() {
-        return Err("pipeline must not be empty".into());
+        return Err(AppError::invalid_pipeline("pipeline must not be empty"));
+    }
+    if !matches!(stages.last(), Some(PipelineStage::Save)) {
+        return Err(AppError::invalid_pipeline("pipeline must end with Save"));
     }
 
-    let mut save_count = 0usize;
-    let mut prev_order = 0u8;
-    let mut first = true;
-
-    for stage in stages {
-        if *stage == PipelineStageKind::Save {
-            save_count += 1;
+    let mut compressed = false;
+    let mut watermarked = false;
+    for (index, stage) in stages.iter().enumerate() {
+        let kind = stage.kind();
+        let invalid = |message: &str| {
+            Err(AppError::invalid_pipeline(format!("stage {index}: {message}")).with_stage(kind))
+        };
+        if compressed && !matches!(stage, PipelineStage::Save) {
+            return invalid("only Save may follow Compress");
         }
-
-        let order = stage.order();
-        if first {
-            prev_order = order;
-            first = false;
-            continue;
+        match stage {
+            PipelineStage::Crop { region } => match region {
+                None => return invalid("Crop requires a region"),
+                Some(region) if region.width == 0 || region.height == 0 => {
+                    return invalid("crop width and height must be > 0")
+                }
+                Some(_) => {}
+            },
+            PipelineStage::Rotate {
+                degrees,
+                background,
+            } => {
+                if !degrees.is_some_and(f32::is_finite) {
+                    return invalid("Rotate requires finite degrees");
+                }
+                if background
+                    .as_deref()
+                    .is_some_and(|color| parse_color(color).is_none())
+                {
+                    return invalid("Rotate background must be a #rrggbb[aa] color");
+                }
+            }
+            PipelineStage::Flip { direction } => {
+                if direction.is_none() {
+                    return invalid("Flip requires a direction");
+                }
+            }
+            PipelineStage::Resize {
+                mode,
+                width,
+                height,
+                edge,
+                percent,
+                background,
+                ..
+            } => {
+                if let Err(message) = validate_resize(
+                    mode.unwrap_or_default(),
+                    *width,
+                    *height,
+                    *edge,
+                    *percent,
+                    background.as_deref(),
+                ) {
+                    return invalid(message);
+                }
+                if watermarked {
+                    return invalid("Watermark must come after Resize");
+                }
+            }
+            PipelineStage::Pad {
+                width,
+                height,
+                aspect,
+                background,
+                ..
+            } => {
+                let has_size = width.is_some() || height.is_some();
+                if has_size == aspect.is_some() {
+                    return invalid("Pad takes either width/height or aspect");
+                }
+                if *width == Some(0) || *height == Some(0) {
+                    return invalid("pad width and height must be > 0");
+                }
+                if aspect.as_deref().is_some_and(|a| parse_aspect(a).is_none()) {
+                    return invalid("Pad aspect must look like 4:5");
+                }
+                if background
+                    .as_deref()
+                    .is_some_and(|bg| bg != "blur" && parse_color(bg).is_none())
+                {
+                    return invalid("Pad background must be a color, transparent or blur");
+                }
+            }
+            PipelineStage::Adjust(params) => {
+                if let Err(message) = validate_adjust(params) {
+                    return invalid(message);
+                }
+            }
+            PipelineStage::Filter(params) => {
+                if let Err(message) = validate_filter(params) {
+                    return invalid(message);
+                }
+            }
+            PipelineStage::Watermark {
+                image,
+                text,
+                font,
+                color,
+                opacity,
+                scale,
+                ..
+            } => {
+                if let Err(message) = validate_watermark(
+                    image.as_deref(),
+                    text.as_deref(),
+                    font.as_deref(),
+                    color.as_deref(),
+                    *opacity,
+                    *scale,
+                ) {
+                    return invalid(&message);
+                }
+                watermarked = true;
+            }
+            PipelineStage::Convert { format } => {
+                if format.is_none() {
+                    return invalid("Convert requires a format");
+                }
+            }
+            PipelineStage::Compress { quality, .. } => {
+                if quality.is_some_and(|quality| !(1..=100).contains(&quality)) {
+                    return invalid("quality must be in [1, 100]");
+                }
+                compressed = true;
+            }
+            PipelineStage::Save => {
+                if index + 1 != stages.len() {
+                    return invalid("Save must be the last stage");
+                }
+            }
         }
+    }
+
+    Ok(())
+}
+
+fn has_resize_size(options: &ProcessOptions) -> bool {
+    options.width.is_some()
+        || options.height.is_some()
+        || options.resize_edge.is_some()
+        || options.resize_percent.is_some()
+}
 
-        if order < prev_order {
-            return Err("pipeline stage order is invalid".into());
+/// 按缩放方式检查所需的尺寸参数是否齐全。
+fn validate_resize(
+    mode: ResizeMode,
+    width: Option<u32>,
+    height: Option<u32>,
+    edge: Option<u32>,
+    percent: Option<f32>,
+    background: Option<&str>,
+) -> Result<(), &'static str> {
+    if width == Some(0) || height == Some(0) {
+        return Err("resize width and height must be > 0");
+    }
+    if edge == Some(0) {
+        return Err("resize edge must be > 0");
+    }
+    if percent.is_some_and(|percent| !percent.is_finite() || percent <= 0.0) {
+        return Err("resize percent must be > 0");
+    }
+    if background.is_some_and(|color| parse_color(color).is_none()) {
+        return Err("resize background must be a #rrggbb[aa] color");
+    }
+    match mode {
+        ResizeMode::Exact | ResizeMode::Fit if width.is_none() && height.is_none() => {
+            Err("resize requires width or height")
+        }
+        ResizeMode::Cover | ResizeMode::Pad if width.is_none() || height.is_none() => {
+            Err("cover and pad resize require both width and height")
         }
-        prev_order = order;
+        ResizeMode::LongestEdge | ResizeMode::ShortestEdge if edge.is_none() => {
+            Err("edge resize requires an edge length")
+        }
+        ResizeMode::Percentage if percent.is_none() => Err("percentage resize requires a percent"),
+        _ => Ok(()),
+    }
+}
+
+fn validate_adjust(params: &AdjustParams) -> Result<(), &'static str> {
+    let in_unit_range = |value: Option<f32>| value.is_none_or(|v| (-1.0..=1.0).contains(&v));
+    if !in_unit_range(params.brightness)
+        || !in_unit_range(params.contrast)
+        || !in_unit_range(params.saturation)
+    {
+        return Err("brightness, contrast and saturation must be in [-1, 1]");
+    }
+    if params.hue_rotate.is_some_and(|hue| !hue.is_finite()) {
+        return Err("hue rotation must be finite");
+    }
+    if params
+        .gamma
+        .is_some_and(|gamma| !(gamma > 0.0 && gamma <= 10.0))
+    {
+        return Err("gamma must be in (0, 10]");
     }
+    Ok(())
+}
 
-    if save_count != 1 {
-        return Err("pipeline must contain Save stage exactly once".into());
+fn validate_filter(params: &FilterParams) -> Result<(), &'static str> {
+    if params.is_empty() {
+        return Err("Filter requires denoise, blur or unsharp");
+    }
+    if params
+        .denoise
+        .is_some_and(|strength| !(0.0..=1.0).contains(&strength))
+    {
+        return Err("denoise must be in [0, 1]");
+    }
+    if params
+        .blur
+        .is_some_and(|sigma| !(sigma > 0.0 && sigma <= 100.0))
+    {
+        return Err("blur sigma must be in (0, 100]");
     }
+    if let Some(unsharp) = &params.unsharp {
+        if !(unsharp.amount > 0.0 && unsharp.amount <= 10.0) {
+            return Err("unsharp amount must be in (0, 10]");
+        }
+        if !(unsharp.radius > 0.0 && unsharp.radius <= 100.0) {
+            return Err("unsharp radius must be in (0, 100]");
+        }
+    }
+    Ok(())
+}
 
+/// 水印来源二选一且文件存在，透明度与比例在有效范围内。
+fn validate_watermark(
+    image: Option<&str>,
+    text: Option<&str>,
+    font: Option<&str>,
+    color: Option<&str>,
+    opacity: Option<f32>,
+    scale: Option<f32>,
+) -> Result<(), String> {
+    match (image, text) {
+        (Some(_), Some(_)) => return Err("Watermark takes either image or text".into()),
+        (None, None) => return Err("Watermark requires an image or text".into()),
+        (None, Some(text)) if text.trim().is_empty() => {
+            return Err("Watermark text must not be empty".into())
+        }
+        _ => {}
+    }
+    for path in image.into_iter().chain(font) {
+        if !Path::new(path).is_file() {
+            return Err(format!("watermark file not found: {path}"));
+        }
+    }
+    if color.is_some_and(|color| parse_color(color).is_none()) {
+        return Err("Watermark color must be a #rrggbb[aa] color".into());
+    }
+    if opacity.is_some_and(|opacity| !(0.0..=1.0).contains(&opacity)) {
+        return Err("Watermark opacity must be in [0, 1]".into());
+    }
+    if scale.is_some_and(|scale| !(scale > 0.0 && scale <= 1.0)) {
+        return Err("Watermark scale must be in (0, 1]".into());
+    }
     Ok(())
 }
 
+/// 流水线最终输出的格式：最后一个 Convert 的格式，没有则沿用输入扩展名。
+pub fn resolve_output_format(input_path: &str, stages: &[PipelineStage]) -> String {
+    stages
+        .iter()
+        .rev()
+        .find_map(|stage| match stage {
+            PipelineStage::Convert { format } => format.clone(),
+            _ => None,
+        })
+        .unwrap_or_else(|| crate::core::image::resolve_output_format(input_path, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn stages(value: Value) -> Vec<PipelineStage> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn request(value: Value) -> ImageJobRequest {
+        let mut value = value;
+        value["inputs"] = json!([]);
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn kinds(stages: &[PipelineStage]) -> Vec<PipelineStageKind> {
+        stages.iter().map(PipelineStage::kind).collect()
+    }
+
+    #[test]
+    fn accepts_a_complete_pipeline() {
+        let pipeline = stages(json!([
+            { "kind": "crop", "region": { "x": 0, "y": 0, "width": 10, "height": 10 } },
+            { "kind": "rotate", "degrees": 12.5, "background": "#ffffff80" },
+            { "kind": "flip", "direction": "horizontal" },
+            { "kind": "resize", "mode": "cover", "width": 800, "height": 600 },
+            { "kind": "pad", "aspect": "4:5", "background": "blur" },
+            { "kind": "adjust", "brightness": 0.2, "gamma": 1.2 },
+            { "kind": "filter", "unsharp": { "amount": 1.0, "radius": 1.5 } },
+            { "kind": "watermark", "text": "cruncher", "opacity": 0.4 },
+            { "kind": "convert", "format": "webp" },
+            { "kind": "compress", "quality": 80 },
+            { "kind": "save" },
+        ]));
+        assert!(validate_pipeline(&pipeline).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_pipelines() {
+        let cases = [
+            (json!([]), "must not be empty", None),
+            (json!([{ "kind": "compress" }]), "must end with Save", None),
+            (
+                json!([{ "kind": "save" }, { "kind": "save" }]),
+                "stage 0: Save must be the last stage",
+                Some(PipelineStageKind::Save),
+            ),
+            (
+                json!([{ "kind": "compress" }, { "kind": "resize", "width": 10 }, { "kind": "save" }]),
+                "stage 1: only Save may follow Compress",
+                Some(PipelineStageKind::Resize),
+            ),
+            (
+                json!([{ "kind": "compress" }, { "kind": "compress" }, { "kind": "save" }]),
+                "stage 1: only Save may follow Compress",
+                Some(PipelineStageKind::Compress),
+            ),
+            (
+                json!([{ "kind": "watermark", "text": "x" }, { "kind": "resize", "width": 10 }, { "kind": "save" }]),
+                "stage 1: Watermark must come after Resize",
+                Some(PipelineStageKind::Resize),
+            ),
+            (
+                json!([{ "kind": "crop" }, { "kind": "save" }]),
+                "Crop requires a region",
+                Some(PipelineStageKind::Crop),
+            ),
+            (
+                json!([{ "kind": "crop", "region": { "x": 0, "y": 0, "width": 0, "height": 5 } }, { "kind": "save" }]),
+                "crop width and height must be > 0",
+                Some(PipelineStageKind::Crop),
+            ),
+            (
+                json!([{ "kind": "rotate" }, { "kind": "save" }]),
+                "Rotate requires finite degrees",
+                Some(PipelineStageKind::Rotate),
+            ),
+            (
+                json!([{ "kind": "rotate", "degrees": 10, "background": "red" }, { "kind": "save" }]),
+                "Rotate background",
+                Some(PipelineStageKind::Rotate),
+            ),
+            (
+                json!([{ "kind": "flip" }, { "kind": "save" }]),
+                "Flip requires a direction",
+                Some(PipelineStageKind::Flip),
+            ),
+            (
+                json!([{ "kind": "resize" }, { "kind": "save" }]),
+                "resize requires width or height",
+                Some(PipelineStageKind::Resize),
+            ),
+            (
+                json!([{ "kind": "resize", "mode": "cover", "width": 10 }, { "kind": "save" }]),
+                "require both width and height",
+                Some(PipelineStageKind::Resize),
+            ),
+            (
+                json!([{ "kind": "resize", "mode": "longestEdge", "width": 10 }, { "kind": "save" }]),
+                "edge resize requires an edge length",
+                Some(PipelineStageKind::Resize),
+            ),
+            (
+                json!([{ "kind": "resize", "mode": "percentage", "percent": 0 }, { "kind": "save" }]),
+                "resize percent must be > 0",
+                Some(PipelineStageKind::Resize),
+            ),
+            (
+                json!([{ "kind": "pad", "width": 10, "aspect": "1:1" }, { "kind": "save" }]),
+                "either width/height or aspect",
+                Some(PipelineStageKind::Pad),
+            ),
+            (
+                json!([{ "kind": "pad" }, { "kind": "save" }]),
+                "either width/height or aspect",
+                Some(PipelineStageKind::Pad),
+            ),
+            (
+                json!([{ "kind": "pad", "aspect": "0:1" }, { "kind": "save" }]),
+                "Pad aspect must look like 4:5",
+                Some(PipelineStageKind::Pad),
+            ),
+            (
+                json!([{ "kind": "pad", "width": 10, "background": "sparkle" }, { "kind": "save" }]),
+                "Pad background",
+                Some(PipelineStageKind::Pad),
+            ),
+            (
+                json!([{ "kind": "adjust", "contrast": 1.5 }, { "kind": "save" }]),
+                "must be in [-1, 1]",
+                Some(PipelineStageKind::Adjust),
+            ),
+            (
+                json!([{ "kind": "adjust", "gamma": 0 }, { "kind": "save" }]),
+                "gamma must be in (0, 10]",
+                Some(PipelineStageKind::Adjust),
+            ),
+            (
+                json!([{ "kind": "filter" }, { "kind": "save" }]),
+                "Filter requires denoise, blur or unsharp",
+                Some(PipelineStageKind::Filter),
+            ),
+            (
+                json!([{ "kind": "filter", "denoise": 2 }, { "kind": "save" }]),
+                "denoise must be in [0, 1]",
+                Some(PipelineStageKind::Filter),
+            ),
+            (
+                json!([{ "kind": "watermark", "text": "x", "image": "/logo.png" }, { "kind": "save" }]),
+                "either image or text",
+                Some(PipelineStageKind::Watermark),
+            ),
+            (
+                json!([{ "kind": "watermark", "text": "  " }, { "kind": "save" }]),
+                "text must not be empty",
+                Some(PipelineStageKind::Watermark),
+            ),
+            (
+                json!([{ "kind": "watermark", "image": "/no/such/logo.png" }, { "kind": "save" }]),
+                "watermark file not found",
+                Some(PipelineStageKind::Watermark),
+            ),
+            (
+                json!([{ "kind": "watermark", "text": "x", "opacity": 1.5 }, { "kind": "save" }]),
+                "opacity must be in [0, 1]",
+                Some(PipelineStageKind::Watermark),
+            ),
+            (
+                json!([{ "kind": "convert" }, { "kind": "save" }]),
+                "Convert requires a format",
+                Some(PipelineStageKind::Convert),
+            ),
+            (
+                json!([{ "kind": "compress", "quality": 0 }, { "kind": "save" }]),
+                "quality must be in [1, 100]",
+                Some(PipelineStageKind::Compress),
+            ),
+        ];
+        for (pipeline, message, stage) in cases {
+            let err = validate_pipeline(&stages(pipeline.clone())).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidPipeline, "{pipeline}");
+            assert!(err.message.contains(message), "{pipeline}: {}", err.message);
+            assert_eq!(err.stage, stage, "{pipeline}");
+        }
+    }
+
+    #[test]
+    fn derives_compress_and_save_by_default() {
+        let resolved = resolve_pipeline(&request(json!({})));
+        assert_eq!(
+            resolved,
+            [
+                PipelineStage::Compress {
+                    mode: Some(CompressMode::VisuallyLossless),
+                    quality: None,
+                },
+                PipelineStage::Save,
+            ]
+        );
+    }
+
+    #[test]
+    fn derives_stages_from_request_options() {
+        let resolved = resolve_pipeline(&request(json!({
+            "mode": "lossless",
+            "cropRegion": { "x": 1, "y": 2, "width": 3, "height": 4 },
+            "options": {
+                "width": 800,
+                "resizeMode": "cover",
+                "height": 600,
+                "quality": 70,
+                "format": "WebP",
+                "adjust": { "saturation": -0.5 },
+            },
+        })));
+        assert_eq!(
+            kinds(&resolved),
+            [
+                PipelineStageKind::Crop,
+                PipelineStageKind::Resize,
+                PipelineStageKind::Adjust,
+                PipelineStageKind::Convert,
+                PipelineStageKind::Compress,
+                PipelineStageKind::Save,
+            ]
+        );
+        assert!(matches!(
+            &resolved[0],
+            PipelineStage::Crop { region: Some(region) } if region.width == 3
+        ));
+        assert!(matches!(
+            resolved[1],
+            PipelineStage::Resize {
+                mode: Some(ResizeMode::Cover),
+                width: Some(800),
+                height: Some(600),
+                ..
+            }
+        ));
+        assert_eq!(
+            resolved[3],
+            PipelineStage::Convert {
+                format: Some("webp".to_string())
+            }
+        );
+        assert_eq!(
+            resolved[4],
+            PipelineStage::Compress {
+                mode: Some(CompressMode::Lossless),
+                quality: Some(70),
+            }
+        );
+        assert!(validate_pipeline(&resolved).is_ok());
+    }
+
+    #[test]
+    fn identity_adjust_adds_no_stage() {
+        let resolved = resolve_pipeline(&request(json!({
+            "options": { "adjust": { "brightness": 0 } },
+        })));
+        assert_eq!(
+            kinds(&resolved),
+            [PipelineStageKind::Compress, PipelineStageKind::Save]
+        );
+    }
+
+    #[test]
+    fn inserts_convert_and_compress_into_explicit_pipelines() {
+        let resolved = resolve_pipeline(&request(json!({
+            "options": { "format": "png" },
+            "pipeline": [{ "kind": "flip", "direction": "vertical" }, { "kind": "save" }],
+        })));
+        assert_eq!(
+            kinds(&resolved),
+            [
+                PipelineStageKind::Flip,
+                PipelineStageKind::Convert,
+                PipelineStageKind::Compress,
+                PipelineStageKind::Save,
+            ]
+        );
+
+        // 已有 Convert 时不再插入；阶段写 `auto` 视为未指定，取请求级格式。
+        let resolved = resolve_pipeline(&request(json!({
+            "options": { "format": "jpeg" },
+            "pipeline": [{ "kind": "convert", "format": "AUTO" }, { "kind": "compress" }, { "kind": "save" }],
+        })));
+        assert_eq!(
+            kinds(&resolved),
+            [
+                PipelineStageKind::Convert,
+                PipelineStageKind::Compress,
+                PipelineStageKind::Save,
+            ]
+        );
+        assert_eq!(
+            resolved[0],
+            PipelineStage::Convert {
+                format: Some("jpeg".to_string())
+            }
+        );
+
+        // 没有 Save 时不补 Compress，交给校验报错。
+        let resolved = resolve_pipeline(&request(json!({
+            "pipeline": [{ "kind": "flip", "direction": "vertical" }],
+        })));
+        assert_eq!(kinds(&resolved), [PipelineStageKind::Flip]);
+        assert!(validate_pipeline(&resolved).is_err());
+    }
+
+    #[test]
+    fn resize_stage_keeps_its_own_size_and_mode() {
+        let resolved = resolve_pipeline(&request(json!({
+            "options": { "width": 800, "resizeMode": "cover", "height": 600, "noUpscale": true },
+            "pipeline": [{ "kind": "resize", "percent": 50, "mode": "percentage" }, { "kind": "save" }],
+        })));
+        assert!(matches!(
+            resolved[0],
+            PipelineStage::Resize {
+                mode: Some(ResizeMode::Percentage),
+                width: None,
+                height: None,
+                no_upscale: Some(true),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn validates_request_options() {
+        let cases = [
+            (
+                json!({ "options": { "quality": 0 } }),
+                "quality must be in [1, 100]",
+            ),
+            (json!({ "options": { "width": 0 } }), "width must be > 0"),
+            (
+                json!({ "options": { "resizeMode": "longestEdge", "width": 10 } }),
+                "edge resize requires an edge length",
+            ),
+            (
+                json!({ "options": { "adjust": { "brightness": -2 } } }),
+                "must be in [-1, 1]",
+            ),
+            (
+                json!({ "cropRegion": { "x": 0, "y": 0, "width": 5, "height": 0 } }),
+                "crop width and height must be > 0",
+            ),
+        ];
+        for (value, message) in cases {
+            let err = validate_job_settings(&request(value.clone())).unwrap_err();
+            assert_eq!(err.code, ErrorCode::InvalidInput, "{value}");
+            assert!(err.message.contains(message), "{value}: {}", err.message);
+        }
+        assert!(validate_job_settings(&request(json!({ "options": { "quality": 90 } }))).is_ok());
+    }
+}
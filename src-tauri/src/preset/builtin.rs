@@ -0,0 +1,46 @@
+use crate::preset::types::{JobPreset, JobTemplate};
+use crate::{CompressMode, ProcessOptions};
+
+pub const BUILTIN_ID_PREFIX: &str = "builtin-";
+
+pub fn builtin_presets() -> Vec<JobPreset> {
+    vec![
+        builtin(
+            "builtin-web-hero-1920-webp",
+            "Web hero 1920 WebP q80",
+            ProcessOptions {
+                quality: Some(80),
+                format: Some("webp".to_string()),
+                width: Some(1920),
+                no_upscale: Some(true),
+                ..ProcessOptions::default()
+            },
+        ),
+        builtin(
+            "builtin-email-thumbnail-600-jpeg",
+            "Email thumbnail 600px JPEG",
+            ProcessOptions {
+                quality: Some(75),
+                format: Some("jpeg".to_string()),
+                width: Some(600),
+                no_upscale: Some(true),
+                ..ProcessOptions::default()
+            },
+        ),
+    ]
+}
+
+fn builtin(id: &str, name: &str, options: ProcessOptions) -> JobPreset {
+    JobPreset {
+        id: id.to_string(),
+        name: name.to_string(),
+        built_in: true,
+        template: JobTemplate {
+            mode: Some(CompressMode::VisuallyLossless),
+            options: Some(options),
+            ..JobTemplate::default()
+        },
+        created_at_ms: 0,
+        updated_at_ms: 0,
+    }
+}
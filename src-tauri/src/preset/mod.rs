@@ -0,0 +1,3 @@
+pub mod builtin;
+pub mod store;
+pub mod types;
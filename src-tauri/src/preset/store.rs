@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{AppError, ErrorCode};
+use crate::job::manager::now_ms;
+use crate::pipeline::validator::validate_job_template;
+use crate::preset::builtin::{builtin_presets, BUILTIN_ID_PREFIX};
+use crate::preset::types::{JobPreset, JobTemplate};
+
+/// 用户预设保存在 `<root>/presets.json`；内置预设只存在于代码中，列出时排在最前。
+#[derive(Default)]
+pub struct PresetStore {
+    path: Option<PathBuf>,
+    state: Mutex<PresetFile>,
+}
+
+/// `presets.json` 的内容。`next_id` 只增不减，删除预设后新建的预设不会复用旧 id，
+/// 以免仍引用旧 id 的监听文件夹悄悄改用另一个预设。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PresetFile {
+    next_id: u64,
+    presets: Vec<JobPreset>,
+}
+
+impl Default for PresetFile {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            presets: Vec::new(),
+        }
+    }
+}
+
+impl PresetFile {
+    /// `next_id` 至少排在已有预设之后，即使文件被手动编辑过也不会分配出重复的 id。
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut file = serde_json::from_slice::<PresetFile>(bytes).ok()?;
+        let after_existing = file
+            .presets
+            .iter()
+            .filter_map(|p| p.id.strip_prefix("preset-")?.parse::<u64>().ok())
+            .max()
+            .map_or(1, |n| n + 1);
+        file.next_id = file.next_id.max(after_existing);
+        Some(file)
+    }
+
+    fn allocate_id(&mut self) -> String {
+        let id = format!("preset-{}", self.next_id);
+        self.next_id += 1;
+        id
+    }
+}
+
+impl PresetStore {
+    /// 文件不存在或损坏时从空列表开始，不影响内置预设。
+    pub fn open(root: PathBuf) -> Result<Self, AppError> {
+        std::fs::create_dir_all(&root).map_err(|e| AppError::io(e, &root))?;
+        let path = root.join("presets.json");
+        let state = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| PresetFile::parse(&bytes))
+            .unwrap_or_default();
+        Ok(Self {
+            path: Some(path),
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<JobPreset>, AppError> {
+        let guard = self.state.lock().map_err(|_| poisoned())?;
+        let mut presets = builtin_presets();
+        presets.extend(guard.presets.iter().cloned());
+        Ok(presets)
+    }
+
+    pub fn get(&self, id: &str) -> Result<JobPreset, AppError> {
+        if let Some(preset) = builtin_presets().into_iter().find(|p| p.id == id) {
+            return Ok(preset);
+        }
+        let guard = self.state.lock().map_err(|_| poisoned())?;
+        guard
+            .presets
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| preset_not_found(id))
+    }
+
+    pub fn create(&self, name: &str, template: JobTemplate) -> Result<JobPreset, AppError> {
+        let name = validate_name(name)?;
+        validate_job_template(&template)?;
+        let mut guard = self.state.lock().map_err(|_| poisoned())?;
+        let mut next = guard.clone();
+        let now = now_ms();
+        let preset = JobPreset {
+            id: next.allocate_id(),
+            name,
+            built_in: false,
+            template,
+            created_at_ms: now,
+            updated_at_ms: now,
+        };
+        next.presets.push(preset.clone());
+        self.commit(&mut guard, next)?;
+        Ok(preset)
+    }
+
+    pub fn update(
+        &self,
+        id: &str,
+        name: Option<String>,
+        template: Option<JobTemplate>,
+    ) -> Result<JobPreset, AppError> {
+        ensure_not_builtin(id)?;
+        let name = name.as_deref().map(validate_name).transpose()?;
+        if let Some(template) = &template {
+            validate_job_template(template)?;
+        }
+        let mut guard = self.state.lock().map_err(|_| poisoned())?;
+        let mut next = guard.clone();
+        let preset = next
+            .presets
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| preset_not_found(id))?;
+        if let Some(name) = name {
+            preset.name = name;
+        }
+        if let Some(template) = template {
+            preset.template = template;
+        }
+        preset.updated_at_ms = now_ms();
+        let updated = preset.clone();
+        self.commit(&mut guard, next)?;
+        Ok(updated)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), AppError> {
+        ensure_not_builtin(id)?;
+        let mut guard = self.state.lock().map_err(|_| poisoned())?;
+        let mut next = guard.clone();
+        next.presets.retain(|p| p.id != id);
+        if next.presets.len() == guard.presets.len() {
+            return Err(preset_not_found(id));
+        }
+        self.commit(&mut guard, next)
+    }
+
+    /// 导入导出文件中的预设：一律作为新的用户预设加入，重新分配 id，原 id 与内置标记被忽略。
+    /// 任一条目的名称或模板无效时整体不导入。
+    pub fn import(&self, path: &Path) -> Result<Vec<JobPreset>, AppError> {
+        let bytes = std::fs::read(path).map_err(|e| AppError::io(e, path))?;
+        let incoming = serde_json::from_slice::<Vec<JobPreset>>(&bytes).map_err(|e| {
+            AppError::invalid_input(format!("invalid preset file: {e}")).with_path(path)
+        })?;
+        let names = incoming
+            .iter()
+            .map(|preset| {
+                validate_job_template(&preset.template)?;
+                validate_name(&preset.name)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.with_path(path))?;
+
+        let mut guard = self.state.lock().map_err(|_| poisoned())?;
+        let mut next = guard.clone();
+        let now = now_ms();
+        let imported = incoming
+            .into_iter()
+            .zip(names)
+            .map(|(preset, name)| JobPreset {
+                id: next.allocate_id(),
+                name,
+                built_in: false,
+                template: preset.template,
+                created_at_ms: now,
+                updated_at_ms: now,
+            })
+            .collect::<Vec<_>>();
+        next.presets.extend(imported.iter().cloned());
+        self.commit(&mut guard, next)?;
+        Ok(imported)
+    }
+
+    /// 导出指定预设（可包含内置预设）；未指定时导出全部用户预设。
+    pub fn export(&self, path: &Path, ids: Option<&[String]>) -> Result<(), AppError> {
+        let presets = match ids {
+            Some(ids) => ids
+                .iter()
+                .map(|id| self.get(id))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => self.state.lock().map_err(|_| poisoned())?.presets.clone(),
+        };
+        let bytes =
+            serde_json::to_vec_pretty(&presets).map_err(|e| AppError::internal(e.to_string()))?;
+        std::fs::write(path, bytes).map_err(|e| AppError::io(e, path))
+    }
+
+    /// 先落盘再替换内存中的状态，写盘失败时两者保持一致。
+    fn commit(&self, current: &mut PresetFile, next: PresetFile) -> Result<(), AppError> {
+        self.save(&next)?;
+        *current = next;
+        Ok(())
+    }
+
+    /// 先写临时文件再重命名，避免进程中途退出留下半截 JSON。
+    fn save(&self, state: &PresetFile) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let tmp_path = path.with_extension("json.tmp");
+        let bytes =
+            serde_json::to_vec_pretty(state).map_err(|e| AppError::internal(e.to_string()))?;
+        std::fs::write(&tmp_path, bytes).map_err(|e| AppError::io(e, &tmp_path))?;
+        std::fs::rename(&tmp_path, path).map_err(|e| AppError::io(e, path))
+    }
+}
+
+fn poisoned() -> AppError {
+    AppError::internal("Preset store poisoned")
+}
+
+fn preset_not_found(id: &str) -> AppError {
+    AppError::new(ErrorCode::PresetNotFound, format!("Preset not found: {id}"))
+}
+
+fn ensure_not_builtin(id: &str) -> Result<(), AppError> {
+    if id.starts_with(BUILTIN_ID_PREFIX) {
+        return Err(AppError::invalid_input(format!(
+            "Built-in presets cannot be modified: {id}"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("preset name must not be empty"));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::stage::{PipelineStage, PipelineStageKind};
+    use crate::ProcessOptions;
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        for preset in builtin_presets() {
+            assert!(
+                validate_job_template(&preset.template).is_ok(),
+                "{}",
+                preset.id
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected_when_saved() {
+        let store = PresetStore::default();
+        let bad_quality = JobTemplate {
+            options: Some(ProcessOptions {
+                quality: Some(0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(store.create("low", bad_quality.clone()).is_err());
+
+        let compress_before_resize = JobTemplate {
+            pipeline: Some(vec![
+                PipelineStageKind::Compress.into(),
+                PipelineStage::Resize {
+                    mode: None,
+                    width: Some(800),
+                    height: None,
+                    edge: None,
+                    percent: None,
+                    filter: None,
+                    no_upscale: None,
+                    background: None,
+                },
+                PipelineStageKind::Save.into(),
+            ]),
+            ..Default::default()
+        };
+        assert!(store.create("order", compress_before_resize).is_err());
+        assert_eq!(store.list().unwrap().len(), builtin_presets().len());
+
+        let preset = store.create("ok", JobTemplate::default()).unwrap();
+        assert!(store.update(&preset.id, None, Some(bad_quality)).is_err());
+        assert!(store.get(&preset.id).unwrap().template.options.is_none());
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::job::types::ImageJobRequest;
+use crate::pipeline::stage::PipelineStage;
+use crate::{CompressMode, CropRegion, ProcessOptions};
+
+/// 不含输入文件的任务模板，字段与 `ImageJobRequest` 一一对应。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobTemplate {
+    pub output_dir: Option<String>,
+    pub mode: Option<CompressMode>,
+    pub crop_region: Option<CropRegion>,
+    pub options: Option<ProcessOptions>,
+    #[serde(
+        default,
+        deserialize_with = "crate::pipeline::stage::deserialize_stages"
+    )]
+    pub pipeline: Option<Vec<PipelineStage>>,
+    pub max_concurrency: Option<usize>,
+    pub priority: Option<i32>,
+}
+
+impl JobTemplate {
+    /// 以模板补全请求中未设置的字段；请求中已有的值（含 `options` 的各个字段）优先。
+    pub fn apply_to(&self, request: &mut ImageJobRequest) {
+        if request.output_dir.is_none() {
+            request.output_dir = self.output_dir.clone();
+        }
+        if request.mode.is_none() {
+            request.mode = self.mode.clone();
+        }
+        if request.crop_region.is_none() {
+            request.crop_region = self.crop_region.clone();
+        }
+        if let Some(base) = &self.options {
+            let mut options = base.clone();
+            if let Some(patch) = request.options.take() {
+                options.merge_from(patch);
+            }
+            request.options = Some(options);
+        }
+        if request.pipeline.is_none() {
+            request.pipeline = self.pipeline.clone();
+        }
+        if request.max_concurrency.is_none() {
+            request.max_concurrency = self.max_concurrency;
+        }
+        if request.priority.is_none() {
+            request.priority = self.priority;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobPreset {
+    pub id: String,
+    pub name: String,
+    /// 内置预设随应用发布，不可修改或删除。
+    #[serde(default)]
+    pub built_in: bool,
+    pub template: JobTemplate,
+    #[serde(default)]
+    pub created_at_ms: u64,
+    #[serde(default)]
+    pub updated_at_ms: u64,
+}
@@ -1,21 +1,122 @@
 use serde::{Deserialize, Serialize};
 
-use crate::job::types::JobStatus;
+use crate::error::AppError;
+use crate::job::types::{JobFileStats, JobKind, JobState, JobStatus};
 use crate::pipeline::stage::PipelineStageKind;
 
+/// 所有类型任务共用的进度事件，统一以 `job-progress` 发出，按 `kind` 区分媒体类型。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ImageJobProgressEvent {
+pub struct JobProgressEvent {
     pub job_id: String,
+    pub kind: JobKind,
     pub file_index: usize,
     pub total_files: usize,
     pub input_path: Option<String>,
     pub output_path: Option<String>,
+    /// 仅图片任务有流水线阶段。
     pub stage: Option<PipelineStageKind>,
     pub stage_progress: f32,
     pub overall_progress: f32,
     pub status: JobStatus,
     pub message: Option<String>,
-    pub error: Option<String>,
+    pub error: Option<AppError>,
+    /// 仅在文件完成事件中携带。
+    pub stats: Option<JobFileStats>,
+}
+
+/// 批量模式下的中间进度事件（`job-progress-batch`），携带自上次发送以来每个文件的最新状态。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgressBatchEvent {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub total_files: usize,
+    pub overall_progress: f32,
+    pub updates: Vec<JobProgressEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobLifecycleKind {
+    Created,
+    /// 首次拿到文件槽位（含恢复后重新开始）。
+    Started,
+    Paused,
+    FileStarted,
+    /// 文件进入终态：完成、失败、取消或跳过。
+    FileFinished,
+    Finished,
+}
+
+/// 任务生命周期事件（`job-lifecycle`），不受进度节流影响。
+/// 文件级事件的 `status` 为该文件的状态，其余为任务状态。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobLifecycleEvent {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub event: JobLifecycleKind,
+    pub status: JobStatus,
+    pub overall_progress: f32,
+    pub file_index: Option<usize>,
+    pub input_path: Option<String>,
+    pub output_path: Option<String>,
+    pub error: Option<AppError>,
+}
+
+impl JobLifecycleEvent {
+    pub fn new(
+        job_id: &str,
+        kind: JobKind,
+        event: JobLifecycleKind,
+        status: JobStatus,
+        overall_progress: f32,
+    ) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            kind,
+            event,
+            status,
+            overall_progress,
+            file_index: None,
+            input_path: None,
+            output_path: None,
+            error: None,
+        }
+    }
+
+    pub fn with_file(mut self, file_index: usize, input_path: &str) -> Self {
+        self.file_index = Some(file_index);
+        self.input_path = Some(input_path.to_string());
+        self
+    }
+}
+
+/// 通过任务专属 Channel 推送的消息；订阅时先收到一次 `snapshot`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum JobChannelMessage {
+    Snapshot(JobState),
+    Progress(JobProgressEvent),
+    ProgressBatch(JobProgressBatchEvent),
+    Lifecycle(JobLifecycleEvent),
+}
+
+/// 监听目录为新文件创建任务时发出，任务进度仍通过 `job-progress` 推送。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderJobEvent {
+    pub watch_id: String,
+    pub job_id: String,
+    pub input_path: String,
+}
+
+/// 监听目录无法读取（被删除、无权限等）时发出；同一错误持续存在时只发一次。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderErrorEvent {
+    pub watch_id: String,
+    pub error: AppError,
 }
 
@@ -1 +1,3 @@
 pub mod event;
+pub mod subscription;
+pub mod throttle;
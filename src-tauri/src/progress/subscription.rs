@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+use tauri::ipc::Channel;
+
+use crate::error::AppError;
+use crate::job::types::JobState;
+use crate::progress::event::JobChannelMessage;
+
+/// 按任务登记的前端 Channel，只接收该任务的事件。
+#[derive(Default)]
+pub struct JobSubscriptions {
+    channels: Mutex<HashMap<String, Vec<Channel<JobChannelMessage>>>>,
+}
+
+impl JobSubscriptions {
+    /// 在持锁状态下取快照、先发送快照再登记，保证快照之后的更新不会漏发。
+    /// 已结束的任务只发送快照，不再登记。
+    pub fn subscribe<F>(
+        &self,
+        channel: Channel<JobChannelMessage>,
+        snapshot: F,
+    ) -> Result<(), AppError>
+    where
+        F: FnOnce() -> Result<JobState, AppError>,
+    {
+        let mut channels = self.channels.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = snapshot()?;
+        let job_id = state.job_id.clone();
+        let finished = state.completed_at_ms.is_some();
+        channel
+            .send(JobChannelMessage::Snapshot(state))
+            .map_err(|e| AppError::internal(e.to_string()))?;
+        if !finished {
+            channels.entry(job_id).or_default().push(channel);
+        }
+        Ok(())
+    }
+
+    /// 为新建的任务登记 Channel，之后的 created 等事件都会送达。
+    pub fn register(&self, job_id: &str, channel: Channel<JobChannelMessage>) {
+        self.channels
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(job_id.to_string())
+            .or_default()
+            .push(channel);
+    }
+
+    /// 发送失败的 Channel（如窗口已关闭）会被移除。
+    pub fn send(&self, job_id: &str, message: &JobChannelMessage) {
+        let mut channels = self.channels.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(list) = channels.get_mut(job_id) {
+            list.retain(|channel| channel.send(message.clone()).is_ok());
+            if list.is_empty() {
+                channels.remove(job_id);
+            }
+        }
+    }
+
+    /// 任务结束后不会再有事件，释放其全部 Channel。
+    pub fn close(&self, job_id: &str) {
+        self.channels
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(job_id);
+    }
+}
@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// 进度事件节流设置，对之后开始执行的任务生效。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProgressSettings {
+    /// 每个任务每秒最多发出的中间进度事件数；0 表示不限制。文件终态与任务级事件不受限制。
+    pub max_events_per_second: u32,
+    /// 为 true 时中间进度改为 `job-progress-batch` 事件，一次携带多个文件的最新状态。
+    pub batched: bool,
+}
+
+impl Default for ProgressSettings {
+    fn default() -> Self {
+        Self {
+            max_events_per_second: 10,
+            batched: false,
+        }
+    }
+}
+
+/// 单个任务的中间进度节流器：按文件合并，同一文件只保留最新一次更新，每个发送间隔最多放行一次。
+/// 间隔内到达的更新由下一次 `offer` 或定时调用的 `flush_due` 发出，最后一次更新不会滞留。
+pub struct ProgressThrottle<T> {
+    interval: Duration,
+    state: Mutex<ThrottleState<T>>,
+    /// 发出事件期间持有：中间进度批次与文件终态事件互斥，终态不会被更早取出的中间进度超越。
+    /// 只在真正发送时获取，未到发送时间的 `offer` 只占用 `state`。
+    emitting: Mutex<()>,
+}
+
+struct ThrottleState<T> {
+    last_flush: Option<Instant>,
+    /// 尚未发出的更新，按文件去重，最近一次更新排在最后。
+    pending: Vec<(usize, T)>,
+}
+
+impl<T> ThrottleState<T> {
+    fn is_due(&self, now: Instant, interval: Duration) -> bool {
+        self.last_flush
+            .is_none_or(|last| now.duration_since(last) >= interval)
+    }
+}
+
+impl<T> ProgressThrottle<T> {
+    pub fn new(max_events_per_second: u32) -> Self {
+        let interval = if max_events_per_second == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(1) / max_events_per_second
+        };
+        Self {
+            interval,
+            state: Mutex::new(ThrottleState {
+                last_flush: None,
+                pending: Vec::new(),
+            }),
+            emitting: Mutex::new(()),
+        }
+    }
+
+    /// 两次发送之间的最小间隔；不限制时为零，此时每次更新都立即发出。
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// 记录某文件的最新进度；距上次发送已满一个间隔时，取出全部待发送更新交给 `flush`。
+    pub fn offer<F>(&self, file_index: usize, update: T, flush: F)
+    where
+        F: FnOnce(Vec<(usize, T)>),
+    {
+        self.offer_at(Instant::now(), file_index, update, flush);
+    }
+
+    /// 已到发送时间且有待发送更新时交给 `flush`。`flush` 在释放节流状态锁之后执行，
+    /// 期间新的 `offer` 不会被阻塞；应由定时器按 `interval` 调用，补发间隔内积压的最后一次更新。
+    pub fn flush_due<F>(&self, flush: F)
+    where
+        F: FnOnce(Vec<(usize, T)>),
+    {
+        self.flush_due_at(Instant::now(), flush);
+    }
+
+    /// 文件进入终态：先把全部待发送的中间进度交给 `flush`，再执行 `emit` 发出终态事件；
+    /// 与 `flush_due` 互斥，终态事件总会送达，且排在该文件所有中间进度之后。
+    pub fn settle<F, E, R>(&self, flush: F, emit: E) -> R
+    where
+        F: FnOnce(Vec<(usize, T)>),
+        E: FnOnce() -> R,
+    {
+        let _emitting = self.emitting.lock().unwrap_or_else(PoisonError::into_inner);
+        let updates = std::mem::take(
+            &mut self
+                .state
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pending,
+        );
+        if !updates.is_empty() {
+            flush(updates);
+        }
+        emit()
+    }
+
+    fn offer_at<F>(&self, now: Instant, file_index: usize, update: T, flush: F)
+    where
+        F: FnOnce(Vec<(usize, T)>),
+    {
+        {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.pending.retain(|(index, _)| *index != file_index);
+            state.pending.push((file_index, update));
+            if !state.is_due(now, self.interval) {
+                return;
+            }
+        }
+        self.flush_due_at(now, flush);
+    }
+
+    fn flush_due_at<F>(&self, now: Instant, flush: F)
+    where
+        F: FnOnce(Vec<(usize, T)>),
+    {
+        let _emitting = self.emitting.lock().unwrap_or_else(PoisonError::into_inner);
+        let updates = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            if state.pending.is_empty() || !state.is_due(now, self.interval) {
+                return;
+            }
+            state.last_flush = Some(now);
+            std::mem::take(&mut state.pending)
+        };
+        flush(updates);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// 每秒 10 次，即 100ms 一个发送间隔。
+    fn throttle() -> ProgressThrottle<u32> {
+        ProgressThrottle::new(10)
+    }
+
+    #[test]
+    fn first_update_is_sent_immediately() {
+        let throttle = throttle();
+        let sent = RefCell::new(Vec::new());
+        throttle.offer_at(Instant::now(), 0, 5, |updates| {
+            sent.borrow_mut().push(updates)
+        });
+        assert_eq!(*sent.borrow(), [vec![(0, 5)]]);
+    }
+
+    #[test]
+    fn keeps_the_latest_update_per_file_within_a_window() {
+        let throttle = throttle();
+        let start = Instant::now();
+        let sent = RefCell::new(Vec::new());
+        let record = |updates| sent.borrow_mut().push(updates);
+        throttle.offer_at(start, 0, 1, record);
+        throttle.offer_at(start + 10 * MS, 0, 2, record);
+        throttle.offer_at(start + 20 * MS, 1, 7, record);
+        throttle.offer_at(start + 30 * MS, 0, 3, record);
+        assert_eq!(sent.borrow().len(), 1);
+
+        // 下一个间隔开始后的更新连同积压的一起发出，文件按最近更新排序。
+        throttle.offer_at(start + 100 * MS, 1, 8, record);
+        assert_eq!(*sent.borrow(), [vec![(0, 1)], vec![(0, 3), (1, 8)]]);
+    }
+
+    #[test]
+    fn trailing_flush_delivers_the_last_update_of_a_window() {
+        let throttle = throttle();
+        let start = Instant::now();
+        let sent = RefCell::new(Vec::new());
+        let record = |updates| sent.borrow_mut().push(updates);
+        throttle.offer_at(start, 0, 10, record);
+        throttle.offer_at(start + 40 * MS, 0, 90, record);
+
+        throttle.flush_due_at(start + 99 * MS, record);
+        assert_eq!(sent.borrow().len(), 1);
+        throttle.flush_due_at(start + 100 * MS, record);
+        assert_eq!(*sent.borrow(), [vec![(0, 10)], vec![(0, 90)]]);
+
+        // 没有积压时定时器不发送。
+        throttle.flush_due_at(start + 300 * MS, record);
+        assert_eq!(sent.borrow().len(), 2);
+    }
+
+    #[test]
+    fn settle_flushes_pending_updates_before_the_terminal_event() {
+        let throttle = throttle();
+        let start = Instant::now();
+        let events = RefCell::new(Vec::new());
+        let record = |updates: Vec<(usize, u32)>| {
+            events.borrow_mut().extend(
+                updates
+                    .into_iter()
+                    .map(|(i, p)| format!("progress {i} {p}")),
+            )
+        };
+        throttle.offer_at(start, 0, 10, record);
+        throttle.offer_at(start + 10 * MS, 0, 60, record);
+        throttle.offer_at(start + 20 * MS, 1, 30, record);
+
+        let result = throttle.settle(record, || {
+            events.borrow_mut().push("done 0".to_string());
+            42
+        });
+        assert_eq!(result, 42);
+        assert_eq!(
+            *events.borrow(),
+            ["progress 0 10", "progress 0 60", "progress 1 30", "done 0"]
+        );
+
+        // 终态之后不会再补发已发出的中间进度。
+        throttle.flush_due_at(start + 200 * MS, record);
+        throttle.settle(record, || events.borrow_mut().push("done 1".to_string()));
+        assert_eq!(events.borrow().len(), 5);
+    }
+
+    #[test]
+    fn settle_waits_for_a_flush_in_progress() {
+        let throttle = throttle();
+        let events = Mutex::new(Vec::new());
+        let (started, flushing) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                throttle.offer(0, 50, |_| {
+                    started.send(()).unwrap();
+                    std::thread::sleep(Duration::from_millis(50));
+                    events.lock().unwrap().push("progress");
+                });
+            });
+            flushing.recv().unwrap();
+            throttle.settle(|_| {}, || events.lock().unwrap().push("done"));
+        });
+        assert_eq!(*events.lock().unwrap(), ["progress", "done"]);
+    }
+
+    #[test]
+    fn unlimited_rate_sends_every_update() {
+        let throttle = ProgressThrottle::new(0);
+        let now = Instant::now();
+        let sent = RefCell::new(Vec::new());
+        for progress in 0..3 {
+            throttle.offer_at(now, 0, progress, |updates| sent.borrow_mut().push(updates));
+        }
+        assert_eq!(*sent.borrow(), [vec![(0, 0)], vec![(0, 1)], vec![(0, 2)]]);
+        assert_eq!(throttle.interval(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use crate::error::{AppError, ErrorCode};
+use crate::job::manager::now_ms;
+use crate::watch::store::WatchStore;
+use crate::watch::types::{PersistedWatch, ProcessedFile, WatchFolder, WatchFolderRequest};
+
+const DEFAULT_STABLE_MS: u64 = 2000;
+
+#[derive(Clone)]
+pub struct WatchManager {
+    inner: Arc<Mutex<Inner>>,
+    store: Option<Arc<WatchStore>>,
+}
+
+struct Inner {
+    next_id: u64,
+    watches: HashMap<String, ManagedWatch>,
+}
+
+struct ManagedWatch {
+    watch: WatchFolder,
+    processed: HashMap<String, ProcessedFile>,
+    /// 已发现但尚未稳定的文件（不落盘，重启后重新计时）。
+    pending: HashMap<String, PendingFile>,
+}
+
+struct PendingFile {
+    size_bytes: u64,
+    modified_ms: u64,
+    unchanged_since_ms: u64,
+}
+
+/// 扫描得到的、已稳定且未处理过的文件，由调用方为其创建任务。
+pub struct ReadyFile {
+    pub watch_id: String,
+    pub path: String,
+    pub preset_id: String,
+    pub output_dir: String,
+    pub size_bytes: u64,
+    pub modified_ms: u64,
+}
+
+/// 一次扫描的结果。
+#[derive(Default)]
+pub struct WatchScan {
+    pub ready: Vec<ReadyFile>,
+    /// 新出现或有变化的目录读取错误（监听 id 与错误）；与上次相同的错误不重复报告。
+    pub errors: Vec<(String, AppError)>,
+}
+
+impl Default for WatchManager {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                next_id: 1,
+                watches: HashMap::new(),
+            })),
+            store: None,
+        }
+    }
+}
+
+impl WatchManager {
+    pub fn with_store(store: WatchStore) -> Self {
+        let mut watches = HashMap::new();
+        let mut next_id = 1u64;
+        for PersistedWatch { watch, processed } in store.load() {
+            if let Some(n) = parse_watch_number(&watch.watch_id) {
+                next_id = next_id.max(n + 1);
+            }
+            watches.insert(
+                watch.watch_id.clone(),
+                ManagedWatch {
+                    watch,
+                    processed,
+                    pending: HashMap::new(),
+                },
+            );
+        }
+        Self {
+            inner: Arc::new(Mutex::new(Inner { next_id, watches })),
+            store: Some(Arc::new(store)),
+        }
+    }
+
+    /// 登记监听目录；默认把目录中现有的图片记为已处理，只处理之后新增或变更的文件。
+    pub fn add_watch(&self, request: WatchFolderRequest) -> Result<WatchFolder, AppError> {
+        let dir = Path::new(&request.dir);
+        if !dir.is_dir() {
+            return Err(AppError::new(
+                ErrorCode::FileNotFound,
+                format!("watch directory not found: {}", request.dir),
+            )
+            .with_path(dir));
+        }
+        let output_dir = Path::new(&request.output_dir);
+        std::fs::create_dir_all(output_dir).map_err(|e| AppError::io(e, output_dir))?;
+        let same_dir = match (dir.canonicalize(), output_dir.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        };
+        if same_dir {
+            return Err(AppError::invalid_input(
+                "output directory must differ from the watched directory",
+            )
+            .with_path(output_dir));
+        }
+
+        let processed = if request.include_existing.unwrap_or(false) {
+            HashMap::new()
+        } else {
+            list_image_files(&request.dir)?
+                .into_iter()
+                .map(|file| {
+                    let record = ProcessedFile {
+                        size_bytes: file.size_bytes,
+                        modified_ms: file.modified_ms,
+                        job_id: None,
+                    };
+                    (file.path, record)
+                })
+                .collect()
+        };
+
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let watch = WatchFolder {
+            watch_id: format!("watch-{}", guard.next_id),
+            dir: request.dir,
+            preset_id: request.preset_id,
+            output_dir: request.output_dir,
+            stable_ms: request.stable_ms.unwrap_or(DEFAULT_STABLE_MS),
+            paused: false,
+            created_at_ms: now_ms(),
+            processed_files: 0,
+            last_job_id: None,
+            last_error: None,
+        };
+        guard.next_id += 1;
+        guard.watches.insert(
+            watch.watch_id.clone(),
+            ManagedWatch {
+                watch: watch.clone(),
+                processed,
+                pending: HashMap::new(),
+            },
+        );
+        self.persist(&guard);
+        Ok(watch)
+    }
+
+    pub fn list_watches(&self) -> Result<Vec<WatchFolder>, AppError> {
+        let guard = self.inner.lock().map_err(|_| poisoned())?;
+        let mut watches = guard
+            .watches
+            .values()
+            .map(|w| w.watch.clone())
+            .collect::<Vec<_>>();
+        watches.sort_by_key(|w| w.created_at_ms);
+        Ok(watches)
+    }
+
+    /// 暂停期间的变化不会丢失：恢复后未记录过的文件照常处理。
+    pub fn set_paused(&self, watch_id: &str, paused: bool) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let managed = guard
+            .watches
+            .get_mut(watch_id)
+            .ok_or_else(|| watch_not_found(watch_id))?;
+        managed.watch.paused = paused;
+        managed.pending.clear();
+        self.persist(&guard);
+        Ok(())
+    }
+
+    pub fn remove_watch(&self, watch_id: &str) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        guard
+            .watches
+            .remove(watch_id)
+            .ok_or_else(|| watch_not_found(watch_id))?;
+        self.persist(&guard);
+        Ok(())
+    }
+
+    /// 扫描所有未暂停的目录，返回大小与修改时间已持续 `stable_ms` 未变、且未按此版本处理过的图片。
+    /// 目录 IO 在锁外进行。
+    pub fn scan(&self) -> WatchScan {
+        let targets = match self.inner.lock() {
+            Ok(guard) => guard
+                .watches
+                .values()
+                .filter(|w| !w.watch.paused)
+                .map(|w| (w.watch.watch_id.clone(), w.watch.dir.clone()))
+                .collect::<Vec<_>>(),
+            Err(_) => return WatchScan::default(),
+        };
+        let listings = targets
+            .into_iter()
+            .map(|(watch_id, dir)| (watch_id, list_image_files(&dir)))
+            .collect::<Vec<_>>();
+
+        let Ok(mut guard) = self.inner.lock() else {
+            return WatchScan::default();
+        };
+        let now = now_ms();
+        let mut scan = WatchScan::default();
+        let mut dirty = false;
+        for (watch_id, listing) in listings {
+            let Some(managed) = guard.watches.get_mut(&watch_id) else {
+                continue;
+            };
+            if managed.watch.paused {
+                continue;
+            }
+            let files = match listing {
+                Ok(files) => files,
+                Err(err) => {
+                    let repeated =
+                        managed.watch.last_error.as_ref().is_some_and(|last| {
+                            last.code == err.code && last.message == err.message
+                        });
+                    if !repeated {
+                        managed.watch.last_error = Some(err.clone());
+                        scan.errors.push((watch_id, err));
+                        dirty = true;
+                    }
+                    continue;
+                }
+            };
+
+            // 已删除文件的记录随之清理，避免记录表无限增长。
+            let before = managed.processed.len();
+            let listed = files
+                .iter()
+                .map(|file| file.path.as_str())
+                .collect::<HashSet<_>>();
+            managed
+                .processed
+                .retain(|path, _| listed.contains(path.as_str()));
+            dirty |= managed.processed.len() != before;
+
+            let mut pending = HashMap::new();
+            for file in files {
+                let seen = managed.processed.get(&file.path).is_some_and(|record| {
+                    record.size_bytes == file.size_bytes && record.modified_ms == file.modified_ms
+                });
+                if seen {
+                    continue;
+                }
+                // 至少在两次扫描中都保持不变，且持续 `stable_ms` 后才视为写入完成。
+                let unchanged_since_ms = match managed.pending.get(&file.path) {
+                    Some(previous)
+                        if previous.size_bytes == file.size_bytes
+                            && previous.modified_ms == file.modified_ms =>
+                    {
+                        previous.unchanged_since_ms
+                    }
+                    _ => now,
+                };
+                let stable =
+                    unchanged_since_ms < now && now - unchanged_since_ms >= managed.watch.stable_ms;
+                if stable {
+                    scan.ready.push(ReadyFile {
+                        watch_id: watch_id.clone(),
+                        path: file.path,
+                        preset_id: managed.watch.preset_id.clone(),
+                        output_dir: managed.watch.output_dir.clone(),
+                        size_bytes: file.size_bytes,
+                        modified_ms: file.modified_ms,
+                    });
+                    continue;
+                }
+                pending.insert(
+                    file.path,
+                    PendingFile {
+                        size_bytes: file.size_bytes,
+                        modified_ms: file.modified_ms,
+                        unchanged_since_ms,
+                    },
+                );
+            }
+            managed.pending = pending;
+        }
+        if dirty {
+            self.persist(&guard);
+        }
+        scan
+    }
+
+    /// 记录文件已处理；建任务失败也记录，避免同一版本反复失败，文件再次变更后会重试。
+    pub fn mark_processed(
+        &self,
+        file: &ReadyFile,
+        result: Result<&str, &AppError>,
+    ) -> Result<(), AppError> {
+        let mut guard = self.inner.lock().map_err(|_| poisoned())?;
+        let managed = guard
+            .watches
+            .get_mut(&file.watch_id)
+            .ok_or_else(|| watch_not_found(&file.watch_id))?;
+        let job_id = match result {
+            Ok(job_id) => {
+                managed.watch.processed_files += 1;
+                managed.watch.last_job_id = Some(job_id.to_string());
+                managed.watch.last_error = None;
+                Some(job_id.to_string())
+            }
+            Err(err) => {
+                managed.watch.last_error = Some(err.clone().with_path(&file.path));
+                None
+            }
+        };
+        managed.processed.insert(
+            file.path.clone(),
+            ProcessedFile {
+                size_bytes: file.size_bytes,
+                modified_ms: file.modified_ms,
+                job_id,
+            },
+        );
+        self.persist(&guard);
+        Ok(())
+    }
+
+    /// 在持有锁的情况下整份写盘；监听数量与变更频率都很低，无需节流。
+    fn persist(&self, inner: &Inner) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let watches = inner
+            .watches
+            .values()
+            .map(|w| PersistedWatch {
+                watch: w.watch.clone(),
+                processed: w.processed.clone(),
+            })
+            .collect::<Vec<_>>();
+        let _ = store.save(&watches);
+    }
+}
+
+struct ListedFile {
+    path: String,
+    size_bytes: u64,
+    modified_ms: u64,
+}
+
+/// 列出目录下（不递归）扩展名属于 `IMAGE_EXT` 的文件及其大小、修改时间。
+fn list_image_files(dir: &str) -> Result<Vec<ListedFile>, AppError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| AppError::io(e, dir))?;
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_image = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| crate::IMAGE_EXT.contains(&ext.as_str()));
+        if !is_image {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        let Some(path) = path.to_str() else {
+            continue;
+        };
+        let modified_ms = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        files.push(ListedFile {
+            path: path.to_string(),
+            size_bytes: meta.len(),
+            modified_ms,
+        });
+    }
+    Ok(files)
+}
+
+fn poisoned() -> AppError {
+    AppError::internal("Watch manager poisoned")
+}
+
+fn watch_not_found(watch_id: &str) -> AppError {
+    AppError::new(
+        ErrorCode::WatchNotFound,
+        format!("Watch folder not found: {watch_id}"),
+    )
+}
+
+fn parse_watch_number(watch_id: &str) -> Option<u64> {
+    watch_id.strip_prefix("watch-")?.parse().ok()
+}
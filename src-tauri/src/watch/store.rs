@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use crate::error::AppError;
+use crate::watch::types::PersistedWatch;
+
+/// 所有监听目录保存在同一个 JSON 文件中（`<root>/watches.json`）。
+pub struct WatchStore {
+    path: PathBuf,
+}
+
+impl WatchStore {
+    pub fn open(root: PathBuf) -> Result<Self, AppError> {
+        std::fs::create_dir_all(&root).map_err(|e| AppError::io(e, &root))?;
+        Ok(Self {
+            path: root.join("watches.json"),
+        })
+    }
+
+    /// 文件不存在或损坏时返回空列表。
+    pub fn load(&self) -> Vec<PersistedWatch> {
+        std::fs::read(&self.path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// 先写临时文件再重命名，避免进程中途退出留下半截 JSON。
+    pub fn save(&self, watches: &[PersistedWatch]) -> Result<(), AppError> {
+        let tmp_path = self.path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(watches).map_err(|e| AppError::internal(e.to_string()))?;
+        std::fs::write(&tmp_path, bytes).map_err(|e| AppError::io(e, &tmp_path))?;
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| AppError::io(e, &self.path))
+    }
+}
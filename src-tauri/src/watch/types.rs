@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolderRequest {
+    pub dir: String,
+    pub preset_id: String,
+    /// 必须与监听目录不同，否则输出文件会被再次当作新图片处理。
+    pub output_dir: String,
+    /// 文件大小与修改时间持续多久不变才开始处理，默认 2 秒。
+    pub stable_ms: Option<u64>,
+    /// 是否处理登记时目录中已有的图片，默认只处理之后新增或变更的。
+    pub include_existing: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolder {
+    pub watch_id: String,
+    pub dir: String,
+    pub preset_id: String,
+    pub output_dir: String,
+    pub stable_ms: u64,
+    pub paused: bool,
+    pub created_at_ms: u64,
+    /// 已为其创建过任务的文件数。
+    #[serde(default)]
+    pub processed_files: usize,
+    pub last_job_id: Option<String>,
+    /// 最近一次扫描或建任务失败的原因，成功后清空。
+    pub last_error: Option<AppError>,
+}
+
+/// 已处理文件的记录：大小或修改时间变化后视为新版本，会再次处理。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessedFile {
+    pub size_bytes: u64,
+    pub modified_ms: u64,
+    /// 登记时已存在而未处理的文件为 None。
+    pub job_id: Option<String>,
+}
+
+/// 落盘的监听记录：配置 + 已处理文件表（键为文件路径）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedWatch {
+    pub watch: WatchFolder,
+    #[serde(default)]
+    pub processed: HashMap<String, ProcessedFile>,
+}
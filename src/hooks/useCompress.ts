@@ -2,14 +2,15 @@ import { useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { compressImage } from "../utils/compressImage";
+import { getErrorMessage } from "../utils/errors";
 import type {
   CompressTask,
   CompressMode,
   CropRegion,
   ImagePipelineStage,
-  ImageJobProgressEvent,
+  JobProgressEvent,
   ImageJobRequest,
-  ImageJobState,
+  JobState,
 } from "../types";
 
 const IMAGE_EXT = new Set(
@@ -155,8 +156,8 @@ export function useCompress() {
         pipeline,
       };
 
-      const unlisten = await listen<ImageJobProgressEvent>(
-        "image-job-progress",
+      const unlisten = await listen<JobProgressEvent>(
+        "job-progress",
         (event) => {
           if (!targetJobId) return;
           const payload = event.payload;
@@ -236,7 +237,7 @@ export function useCompress() {
                     return {
                       ...item,
                       status: "pending",
-                      error: payload.error ?? item.error,
+                      error: payload.error?.message ?? item.error,
                       progressPercent: undefined,
                       detailJobType: kind,
                     };
@@ -244,7 +245,7 @@ export function useCompress() {
                   return {
                     ...item,
                     status: payload.status === "cancelled" ? "cancelled" : "error",
-                    error: payload.error ?? item.error,
+                    error: payload.error?.message ?? item.error,
                     progressPercent: Math.max(
                       0,
                       Math.min(100, Math.round(payload.stageProgress))
@@ -269,7 +270,7 @@ export function useCompress() {
               kind,
               status: payload.status,
               taskName: task.name,
-              error: payload.error,
+              error: payload.error?.message,
             });
           }
         }
@@ -279,7 +280,7 @@ export function useCompress() {
         targetJobId = await invoke<string>("create_image_job", { request });
       } catch (error) {
         unlisten();
-        const message = error instanceof Error ? error.message : String(error);
+        const message = getErrorMessage(error);
         setTasks((prev) =>
           prev.map((item) => {
             if (item.id !== task.id) return item;
@@ -381,7 +382,7 @@ export function useCompress() {
               ? {
                   ...t,
                   status: "error",
-                  error: e instanceof Error ? e.message : String(e),
+                  error: getErrorMessage(e),
                   progressPercent: t.progressPercent ?? 0,
                 }
               : t
@@ -434,8 +435,8 @@ export function useCompress() {
 
         try {
           await new Promise<void>(async (resolve, reject) => {
-            const unlisten = await listen<ImageJobProgressEvent>(
-              "image-job-progress",
+            const unlisten = await listen<JobProgressEvent>(
+              "job-progress",
               (event) => {
                 if (!targetJobId) return;
                 const payload = event.payload;
@@ -478,7 +479,11 @@ export function useCompress() {
                     );
                   }
 
-                  if (payload.status === "failed" || payload.status === "cancelled") {
+                  if (
+                    payload.status === "failed" ||
+                    payload.status === "cancelled" ||
+                    payload.status === "skipped"
+                  ) {
                     doneSet.add(task.id);
                     setProgress({ current: doneSet.size, total: pending.length });
                     setTasks((prev) =>
@@ -487,7 +492,7 @@ export function useCompress() {
                           ? {
                               ...t,
                               status: payload.status === "failed" ? "error" : "cancelled",
-                              error: payload.error,
+                              error: payload.error?.message,
                               progressPercent: Math.max(
                                 0,
                                 Math.min(100, Math.round(payload.stageProgress))
@@ -521,7 +526,7 @@ export function useCompress() {
           });
 
           if (targetJobId) {
-            const snapshot = await invoke<ImageJobState>("get_image_job", {
+            const snapshot = await invoke<JobState>("get_image_job", {
               jobId: targetJobId,
             });
             setTasks((prev) =>
@@ -535,7 +540,7 @@ export function useCompress() {
                     ? "done"
                     : file.status === "failed"
                       ? "error"
-                      : file.status === "cancelled"
+                      : file.status === "cancelled" || file.status === "skipped"
                         ? "cancelled"
                         : "compressing";
                 return {
@@ -543,13 +548,13 @@ export function useCompress() {
                   status: nextStatus,
                   outputPath: file.outputPath ?? item.outputPath,
                   progressPercent: Math.max(0, Math.min(100, Math.round(file.progress))),
-                  error: file.error ?? item.error,
+                  error: file.error?.message ?? item.error,
                 };
               })
             );
           }
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = getErrorMessage(error);
           const canFallback = message.includes("create_image_job");
           if (canFallback) {
             for (let i = 0; i < pending.length; i++) {
@@ -4,6 +4,7 @@ import { save } from "@tauri-apps/plugin-dialog";
 import type { PixelCrop, PercentCrop } from "react-image-crop";
 import { convertToPixelCrop } from "react-image-crop";
 import { useI18n } from "../../hooks/useI18n";
+import { getErrorMessage } from "../../utils/errors";
 import type { CompressTask, CropRegion } from "../../types";
 import type { ProcessingSettings } from "./types";
 import { DEFAULT_WIDTH, DEFAULT_HEIGHT } from "./types";
@@ -253,7 +254,7 @@ export function ImageDetailPage({
       setSubmitToast("crop");
     } catch (e) {
       setCropToast("error");
-      setCropErrorMsg(e instanceof Error ? e.message : String(e));
+      setCropErrorMsg(getErrorMessage(e));
     } finally {
       setIsApplyingCrop(false);
     }
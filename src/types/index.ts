@@ -13,8 +13,64 @@ export interface ProcessOptions {
   format?: string;
   width?: number;
   height?: number;
+  /** Apply the EXIF orientation when decoding; defaults to true */
+  autoOrient?: boolean;
+  /** Defaults to "fit": scale to fit inside width x height (a missing side is unbounded) */
+  resizeMode?: ResizeMode;
+  /** Target edge length for "longestEdge" / "shortestEdge" */
+  resizeEdge?: number;
+  /** Scale for "percentage" */
+  resizePercent?: number;
+  resizeFilter?: ResizeFilter;
+  /** Never enlarge beyond the source size */
+  noUpscale?: boolean;
+  /** Fill colour for "pad" (#rrggbb[aa]); defaults to transparent */
+  resizeBackground?: string;
+  /** Colour adjustments; adds an adjust stage after resizing in the automatic pipeline */
+  adjust?: AdjustParams;
 }
 
+/** Applied in order: denoise, blur, unsharp; unset ones are skipped */
+export interface FilterParams {
+  /** 0-1 blend of a 3x3 median filter */
+  denoise?: number;
+  /** Gaussian blur sigma in pixels */
+  blur?: number;
+  unsharp?: {
+    amount: number;
+    /** Blur sigma used to extract detail, in pixels */
+    radius: number;
+    /** Differences at or below this (0-255) are left alone */
+    threshold?: number;
+  };
+}
+
+/** Unset fields leave the image unchanged */
+export interface AdjustParams {
+  /** -1 to 1, 0 = unchanged */
+  brightness?: number;
+  /** -1 to 1, 0 = unchanged */
+  contrast?: number;
+  /** -1 to 1, 0 = unchanged, -1 = fully desaturated */
+  saturation?: number;
+  /** Degrees */
+  hueRotate?: number;
+  /** Greater than 0, 1 = unchanged */
+  gamma?: number;
+  grayscale?: boolean;
+  sepia?: boolean;
+  invert?: boolean;
+}
+
+export type ResizeMode =
+  | "exact"
+  | "fit"
+  | "cover"
+  | "pad"
+  | "longestEdge"
+  | "shortestEdge"
+  | "percentage";
+
 export type TaskType = "image" | "video";
 
 export type TaskStatus =
@@ -53,9 +109,98 @@ export interface FfmpegCheckResult {
   version: string;
 }
 
-export type ImagePipelineStage = "crop" | "resize" | "convert" | "compress" | "save";
+export type ImagePipelineStage =
+  | "crop"
+  | "rotate"
+  | "flip"
+  | "resize"
+  | "pad"
+  | "adjust"
+  | "filter"
+  | "watermark"
+  | "convert"
+  | "compress"
+  | "save";
+
+export type FlipDirection = "horizontal" | "vertical";
 
-export type ImageJobStatus = "pending" | "running" | "completed" | "failed" | "cancelled";
+export type Anchor =
+  | "topLeft"
+  | "top"
+  | "topRight"
+  | "left"
+  | "center"
+  | "right"
+  | "bottomLeft"
+  | "bottom"
+  | "bottomRight";
+
+export type ResizeFilter = "nearest" | "triangle" | "catmullRom" | "gaussian" | "lanczos3";
+
+/** A pipeline stage with its own parameters; omitted ones fall back to the request-level settings */
+export type ImagePipelineStageSpec =
+  | { kind: "crop"; region?: CropRegion }
+  /** Clockwise; non-right angles expand the canvas and fill corners with `background` (#rrggbb[aa], default transparent) */
+  | { kind: "rotate"; degrees: number; background?: string }
+  | { kind: "flip"; direction: FlipDirection }
+  | {
+      kind: "resize";
+      mode?: ResizeMode;
+      width?: number;
+      height?: number;
+      edge?: number;
+      percent?: number;
+      filter?: ResizeFilter;
+      noUpscale?: boolean;
+      background?: string;
+    }
+  /** Extends the canvas to a size or an aspect ratio ("4:5") without cropping; larger images shrink to fit */
+  | {
+      kind: "pad";
+      width?: number;
+      height?: number;
+      aspect?: string;
+      /** Defaults to "center" */
+      anchor?: Anchor;
+      /** #rrggbb[aa], "transparent" or "blur"; defaults to white. JPEG output flattens transparency onto white */
+      background?: string;
+    }
+  /** Unset fields fall back to `options.adjust` */
+  | ({ kind: "adjust" } & AdjustParams)
+  | ({ kind: "filter" } & FilterParams)
+  /** Either `image` (PNG logo path) or `text`; must come after every resize stage */
+  | {
+      kind: "watermark";
+      image?: string;
+      text?: string;
+      /** TTF path; defaults to the bundled font */
+      font?: string;
+      /** Text colour, defaults to white */
+      color?: string;
+      /** Defaults to "bottomRight" */
+      anchor?: Anchor;
+      /** Pixel offsets from the anchored position, x right / y down */
+      offsetX?: number;
+      offsetY?: number;
+      /** 0-1, defaults to 0.5 */
+      opacity?: number;
+      /** Fraction of the shorter side: logo's longer edge or text size (defaults 0.2 / 0.05) */
+      scale?: number;
+      tiled?: boolean;
+    }
+  | { kind: "convert"; format?: string }
+  | { kind: "compress"; mode?: CompressMode; quality?: number }
+  | { kind: "save" };
+
+export type ImageJobStatus =
+  | "pending"
+  | "running"
+  | "paused"
+  | "completed"
+  | "failed"
+  | "cancelled"
+  /** Only used for individual files */
+  | "skipped";
 
 export interface ImageJobRequest {
   inputs: string[];
@@ -63,8 +208,64 @@ export interface ImageJobRequest {
   mode?: CompressMode;
   cropRegion?: CropRegion;
   options?: ProcessOptions;
-  pipeline?: ImagePipelineStage[];
+  pipeline?: (ImagePipelineStage | ImagePipelineStageSpec)[];
   maxConcurrency?: number;
+  /** Higher runs first; defaults to 0 */
+  priority?: number;
+}
+
+/** Video jobs share the job queue, commands and "job-progress" event with image jobs */
+export interface VideoJobRequest {
+  inputs: string[];
+  outputDir?: string;
+  mode?: CompressMode;
+  /** Defaults to 1 */
+  maxConcurrency?: number;
+  priority?: number;
+}
+
+export type JobKind = "image" | "video";
+
+/** Request accepted by create_job; `kind` selects the executor */
+export type JobRequest =
+  | ({ kind: "image" } & ImageJobRequest)
+  | ({ kind: "video" } & VideoJobRequest);
+
+export interface JobRetryOverrides {
+  outputDir?: string;
+  mode?: CompressMode;
+  options?: ProcessOptions;
+  maxConcurrency?: number;
+}
+
+export type ErrorCode =
+  | "cancelled"
+  | "fileNotFound"
+  | "permissionDenied"
+  | "diskFull"
+  | "io"
+  | "unsupportedImage"
+  | "decodeFailed"
+  | "encodeFailed"
+  | "invalidInput"
+  | "cropOutOfBounds"
+  | "invalidPipeline"
+  | "jobNotFound"
+  | "invalidJobState"
+  | "presetNotFound"
+  | "watchNotFound"
+  | "ffmpegFailed"
+  | "internal";
+
+/** Structured error returned by image, pipeline and job commands */
+export interface AppError {
+  code: ErrorCode;
+  message: string;
+  path?: string;
+  stage?: ImagePipelineStage;
+  /** Name of the underlying std::io::ErrorKind, if any */
+  ioKind?: string;
+  jobId?: string;
 }
 
 export interface ImageJobFileState {
@@ -72,12 +273,28 @@ export interface ImageJobFileState {
   outputPath?: string;
   status: ImageJobStatus;
   progress: number;
-  error?: string;
+  error?: AppError;
+  stats?: ImageJobFileStats;
+}
+
+export interface ImageJobFileStats {
+  inputSizeBytes?: number;
+  outputSizeBytes?: number;
+  /** outputSize / inputSize; below 1 means the file got smaller */
+  compressionRatio?: number;
+  outputWidth: number;
+  outputHeight: number;
+  outputFormat: string;
+  durationMs: number;
 }
 
-export interface ImageJobState {
+export interface JobState {
   jobId: string;
+  kind: JobKind;
+  parentJobId?: string;
   status: ImageJobStatus;
+  /** 1-based position among queued jobs that have not started yet */
+  queuePosition?: number;
   createdAtMs: number;
   startedAtMs?: number;
   completedAtMs?: number;
@@ -85,12 +302,19 @@ export interface ImageJobState {
   completedFiles: number;
   failedFiles: number;
   cancelledFiles: number;
+  skippedFiles: number;
   overallProgress: number;
+  totalInputBytes: number;
+  totalOutputBytes: number;
+  totalSavedBytes: number;
+  averageCompressionRatio?: number;
   files: ImageJobFileState[];
 }
 
-export interface ImageJobProgressEvent {
+/** Payload of the "job-progress" event for every job kind; stage is only set for image jobs */
+export interface JobProgressEvent {
   jobId: string;
+  kind: JobKind;
   fileIndex: number;
   totalFiles: number;
   inputPath?: string;
@@ -100,5 +324,143 @@ export interface ImageJobProgressEvent {
   overallProgress: number;
   status: ImageJobStatus;
   message?: string;
-  error?: string;
+  error?: AppError;
+  stats?: ImageJobFileStats;
+}
+
+/** Payload of "job-progress-batch", sent instead of intermediate "job-progress" events in batched mode */
+export interface JobProgressBatchEvent {
+  jobId: string;
+  kind: JobKind;
+  totalFiles: number;
+  overallProgress: number;
+  /** Latest update per file since the previous batch */
+  updates: JobProgressEvent[];
+}
+
+export type JobLifecycleKind =
+  | "created"
+  | "started"
+  | "paused"
+  | "fileStarted"
+  | "fileFinished"
+  | "finished";
+
+/** Payload of "job-lifecycle"; never throttled. `status` is the file's status for file events */
+export interface JobLifecycleEvent {
+  jobId: string;
+  kind: JobKind;
+  event: JobLifecycleKind;
+  status: ImageJobStatus;
+  overallProgress: number;
+  fileIndex?: number;
+  inputPath?: string;
+  outputPath?: string;
+  error?: AppError;
+}
+
+/** Messages on the per-job Channel passed to create_image_job or subscribe_image_job */
+export type JobChannelMessage =
+  | { type: "snapshot"; data: JobState }
+  | { type: "progress"; data: JobProgressEvent }
+  | { type: "progressBatch"; data: JobProgressBatchEvent }
+  | { type: "lifecycle"; data: JobLifecycleEvent };
+
+/** Throttling of intermediate progress events; applies to jobs started afterwards */
+export interface ProgressSettings {
+  /** Per job; 0 disables throttling. File results and job-level events are never dropped. Defaults to 10 */
+  maxEventsPerSecond: number;
+  batched: boolean;
+}
+
+export interface RetentionPolicy {
+  maxJobs?: number;
+  maxAgeMs?: number;
+}
+
+export type ReportFormat = "csv" | "json" | "html";
+
+/** ImageJobRequest without inputs, stored in a preset */
+export interface JobTemplate {
+  outputDir?: string;
+  mode?: CompressMode;
+  cropRegion?: CropRegion;
+  options?: ProcessOptions;
+  pipeline?: (ImagePipelineStage | ImagePipelineStageSpec)[];
+  maxConcurrency?: number;
+  priority?: number;
+}
+
+export interface JobPreset {
+  id: string;
+  name: string;
+  /** Shipped with the app; cannot be updated or deleted */
+  builtIn: boolean;
+  template: JobTemplate;
+  createdAtMs: number;
+  updatedAtMs: number;
+}
+
+export interface FileEstimate {
+  fileIndex: number;
+  inputPath: string;
+  inputSizeBytes?: number;
+  predictedSizeBytes?: number;
+  /** Negative when the output would be larger */
+  predictedSavedBytes?: number;
+  compressionRatio?: number;
+  outputWidth?: number;
+  outputHeight?: number;
+  outputFormat?: string;
+  error?: AppError;
+}
+
+/** Result of estimate_image_job; totals are extrapolated when only a sample was encoded */
+export interface ImageJobEstimate {
+  totalFiles: number;
+  sampledFiles: number;
+  extrapolated: boolean;
+  totalInputBytes: number;
+  /** Absent when no sampled file could be estimated */
+  predictedOutputBytes?: number;
+  predictedSavedBytes?: number;
+  compressionRatio?: number;
+  files: FileEstimate[];
+}
+
+export interface WatchFolderRequest {
+  dir: string;
+  presetId: string;
+  /** Must differ from the watched directory */
+  outputDir: string;
+  /** How long size and mtime must stay unchanged before processing; defaults to 2000 */
+  stableMs?: number;
+  /** Also process images already in the folder; defaults to false */
+  includeExisting?: boolean;
+}
+
+export interface WatchFolder {
+  watchId: string;
+  dir: string;
+  presetId: string;
+  outputDir: string;
+  stableMs: number;
+  paused: boolean;
+  createdAtMs: number;
+  processedFiles: number;
+  lastJobId?: string;
+  lastError?: AppError;
+}
+
+/** Payload of the "watch-folder-job" event */
+export interface WatchFolderJobEvent {
+  watchId: string;
+  jobId: string;
+  inputPath: string;
+}
+
+/** Payload of the "watch-folder-error" event, sent when a watched folder cannot be listed */
+export interface WatchFolderErrorEvent {
+  watchId: string;
+  error: AppError;
 }
@@ -1,11 +1,12 @@
 import { Channel, invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { join, tempDir } from "@tauri-apps/api/path";
+import { getErrorMessage } from "./errors";
 import type {
   CropRegion,
-  ImageJobProgressEvent,
+  JobProgressEvent,
   ImageJobRequest,
-  ImageJobState,
+  JobState,
   ProcessOptions,
 } from "../types";
 
@@ -73,8 +74,8 @@ export async function compressImage(
 
     const result = await new Promise<CompressImageResult>(async (resolve, reject) => {
       let timer: ReturnType<typeof setTimeout> | null = null;
-      const unlisten = await listen<ImageJobProgressEvent>(
-        "image-job-progress",
+      const unlisten = await listen<JobProgressEvent>(
+        "job-progress",
         async (event) => {
           if (!targetJobId || settled) return;
           const payload = event.payload;
@@ -91,7 +92,7 @@ export async function compressImage(
             settled = true;
             if (timer) clearTimeout(timer);
             unlisten();
-            reject(new Error(payload.error ?? "Image job failed"));
+            reject(new Error(payload.error?.message ?? "Image job failed"));
             return;
           }
 
@@ -99,7 +100,7 @@ export async function compressImage(
             settled = true;
             if (timer) clearTimeout(timer);
             unlisten();
-            reject(new Error(payload.error ?? "Image job cancelled"));
+            reject(new Error(payload.error?.message ?? "Image job cancelled"));
             return;
           }
 
@@ -107,7 +108,7 @@ export async function compressImage(
             settled = true;
             if (timer) clearTimeout(timer);
             unlisten();
-            const snapshot = await invoke<ImageJobState>("get_image_job", {
+            const snapshot = await invoke<JobState>("get_image_job", {
               jobId: targetJobId,
             });
             const outputPath = snapshot.files?.[0]?.outputPath ?? fallbackOutputPath;
@@ -140,7 +141,7 @@ export async function compressImage(
 
     return result;
   } catch (error) {
-    const message = error instanceof Error ? error.message : String(error);
+    const message = getErrorMessage(error);
     if (
       !message.includes("create_image_job") &&
       !message.includes("rollback flag")
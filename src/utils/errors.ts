@@ -0,0 +1,17 @@
+import type { AppError } from "../types";
+
+export function isAppError(value: unknown): value is AppError {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    typeof (value as AppError).code === "string" &&
+    typeof (value as AppError).message === "string"
+  );
+}
+
+/** Commands may reject with an AppError object, an Error or a plain string. */
+export function getErrorMessage(error: unknown): string {
+  if (error instanceof Error) return error.message;
+  if (isAppError(error)) return error.message;
+  return String(error);
+}